@@ -1,27 +1,298 @@
-use std::time::Duration;
+use std::{
+    fmt,
+    sync::{
+        mpsc::{self, TryRecvError},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
+use color_eyre::eyre::eyre;
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
-    layout::{Constraint, Direction, Layout},
-    widgets::{Block, Paragraph},
-    Frame,
+    backend::{Backend, TestBackend},
+    buffer::Buffer,
+    crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind},
+    layout::Rect,
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Paragraph, Widget, Wrap},
+    Frame, Terminal,
+};
+use unicode_width::UnicodeWidthChar;
+
+use crate::{
+    capabilities::Capabilities,
+    clipboard::{self, Copied},
+    config::Config,
+    context_menu::ContextMenu,
+    credentials,
+    deep_link::DeepLink,
+    external::{self, Program},
+    frame_budget::{FrameBudget, FrameTimings},
+    highlight,
+    input::{EventSource, TerminalEvents},
+    json_tree::JsonTree,
+    keymap::{self, Chord},
+    links::{self, Link},
+    mirror::Mirror,
+    notifications::NotificationCenter,
+    pane::{Pane, PaneId, ScreenLayout},
+    prepare::{PreparedOutput, Preparer},
+    qr::QrCode,
+    quick_command::{self, CommandEvent},
+    render,
+    render_cache::RenderCache,
+    rules::Level,
+    scroll_view::PAN_COLUMNS,
+    tasks::{self, CancelToken, State},
+    theme::{Role, Theme},
+    timestamp::TimeFormat,
+    toast::Toast,
+    tutorial::{Step, Tutorial},
+    whats_new,
 };
 
+mod counters;
+mod json;
+mod navigation;
+mod notifications;
+mod output;
+
+use counters::Counters;
+use navigation::Screen;
+use output::Output;
+
 pub const API_KEY_LEN: usize = 32;
 
-#[derive(Debug, Copy, Clone)]
+/// How long to wait for the keychain, which may be prompting for unlock.
+const KEYCHAIN_TIMEOUT: Duration = Duration::from_secs(60);
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// How long background tasks get to finish after the TUI closes before
+/// they are reported as leaked.
+const SHUTDOWN_GRACE: Duration = Duration::from_millis(500);
+
+/// How often the loop wakes up without input, and how much slower it goes
+/// while the terminal is unfocused.
+const TICK_RATE: Duration = Duration::from_millis(250);
+const BACKGROUND_TICK_RATE: Duration = Duration::from_secs(1);
+
+/// The steps of `monika tutorial`.
+static TUTORIAL: [Step<Message>; 7] = [
+    Step {
+        text: "Press j to increment the counter.",
+        target: Some(PaneId::Live),
+        done: |msg| matches!(msg, Message::Increment),
+    },
+    Step {
+        text: "Press k to decrement it again.",
+        target: Some(PaneId::Live),
+        done: |msg| matches!(msg, Message::Decrement),
+    },
+    Step {
+        text: "Press Tab to focus the other pane.",
+        target: Some(PaneId::Manual),
+        done: |msg| matches!(msg, Message::FocusNext),
+    },
+    Step {
+        text: "This pane only refreshes when asked. Press r to refresh it.",
+        target: Some(PaneId::Manual),
+        done: |msg| matches!(msg, Message::RefreshFocused),
+    },
+    Step {
+        text: "Press n to see past notifications. Esc closes them.",
+        target: None,
+        done: |msg| matches!(msg, Message::ToggleNotifications),
+    },
+    Step {
+        text: "Press T to show times as local time, UTC or relative.",
+        target: None,
+        done: |msg| matches!(msg, Message::CycleTimeFormat),
+    },
+    Step {
+        text: "That's it! Press q to quit, or keep exploring.",
+        target: None,
+        done: |msg| matches!(msg, Message::Quit),
+    },
+];
+
+/// Draw time above which a frame counts as slow, unless configured.
+const DEFAULT_FRAME_BUDGET_MS: u64 = 16;
+
+#[derive(Debug, Clone)]
 struct Model {
-    counter: i32,
     running_state: RunningState,
     api_key: [u8; API_KEY_LEN],
+    theme: Theme,
+    /// When the last tick was handled.
+    now: Instant,
+    counters: Counters,
+    output: Output,
+    screen: Screen,
+    feedback: Feedback,
+    /// Draw URLs as OSC 8 hyperlinks rather than hinting at `o` to open them.
+    hyperlinks: bool,
+    /// Copy through the terminal (OSC 52) when there is no local clipboard:
+    /// enabled in the config and supported by the terminal.
+    osc52: bool,
+    /// URLs in the last drawn frame.
+    links: Vec<Link>,
+    /// Whether the terminal window has focus, as far as it reports it.
+    terminal_focused: bool,
+    /// Color JSON output by syntax.
+    highlighting: bool,
+    /// The output explored as a JSON tree, if open.
+    json_tree: Option<JsonTree>,
+    /// Every toast of the session, listed on `n`.
+    notifications: NotificationCenter,
+    /// Messages handled this session, for the exit summary.
+    messages: usize,
+    /// Quick commands bound to keys of their own.
+    quick_keys: Vec<(Chord, String)>,
+    time_format: TimeFormat,
+    /// Draw times, shown on the debug screen.
+    frame_budget: FrameBudget,
+    tutorial: Option<Tutorial<Message>>,
 }
 
 impl Model {
-    pub fn new(api_key: [u8; API_KEY_LEN]) -> Self {
+    pub fn new(api_key: [u8; API_KEY_LEN], config: &Config) -> Self {
+        let now = Instant::now();
         Self {
             api_key,
+            theme: Theme::from_preset(config.theme),
             running_state: RunningState::default(),
-            counter: 0,
+            now,
+            counters: Counters::new(config, now),
+            output: Output::default(),
+            screen: Screen::default(),
+            feedback: Feedback::default(),
+            hyperlinks: false,
+            osc52: false,
+            links: Vec::new(),
+            terminal_focused: true,
+            highlighting: !config.disable_syntax_highlighting,
+            json_tree: None,
+            notifications: NotificationCenter::default(),
+            messages: 0,
+            quick_keys: keymap::quick_command_keys(&config.quick_commands),
+            time_format: TimeFormat::default(),
+            frame_budget: FrameBudget::new(Duration::from_millis(
+                config.frame_budget_ms.unwrap_or(DEFAULT_FRAME_BUDGET_MS),
+            )),
+            tutorial: None,
+        }
+    }
+
+    /// A deep link back to the current view.
+    fn deep_link(&self) -> DeepLink {
+        DeepLink {
+            screen: "counter".to_string(),
+            params: vec![(
+                "pane".to_string(),
+                self.screen.focused.index().unwrap_or(0).to_string(),
+            )],
+        }
+    }
+
+    /// Restore the view described by a deep link.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the link names an unknown
+    /// screen, an unknown parameter, or a pane that does not exist.
+    fn with_deep_link(self, link: &DeepLink) -> color_eyre::Result<Self> {
+        if link.screen != "counter" {
+            return Err(eyre!(
+                "Unknown screen `{}` in deep link. Known screens: counter.",
+                link.screen
+            ));
+        }
+
+        let mut model = self;
+        for (key, value) in &link.params {
+            match key.as_str() {
+                "pane" => {
+                    model.screen.focused = PaneId::parse(value).ok_or_else(|| {
+                        eyre!(
+                            "Deep link pane must be between 0 and {}, found `{}`.",
+                            PaneId::COUNTERS.len() - 1,
+                            value
+                        )
+                    })?;
+                }
+                _ => return Err(eyre!("Unknown deep link parameter `{}`.", key)),
+            }
+        }
+        Ok(model)
+    }
+}
+
+/// What happened during a session, printed after leaving the TUI.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Summary {
+    pub duration: Duration,
+    pub messages: usize,
+    pub errors: usize,
+    pub alerts: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.duration.as_secs();
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        write!(f, "Session lasted ")?;
+        if hours > 0 {
+            write!(f, "{}h ", hours)?;
+        }
+        if hours > 0 || minutes > 0 {
+            write!(f, "{}m ", minutes)?;
+        }
+        writeln!(f, "{}s", seconds)?;
+        writeln!(f, "  Messages processed: {}", self.messages)?;
+        writeln!(f, "  Errors: {}", self.errors)?;
+        write!(f, "  Threshold alerts: {}", self.alerts)
+    }
+}
+
+/// The toast in the status line and the errors shown so far, which every
+/// domain reports to.
+#[derive(Debug, Clone, Default)]
+struct Feedback {
+    toast: Option<Toast>,
+    /// Errors shown this session.
+    errors: usize,
+}
+
+impl Feedback {
+    fn show(&mut self, role: Role, text: impl Into<String>, now: Instant) {
+        self.toast = Some(Toast::new(role, text, now));
+    }
+
+    /// Show an error, counting it for the exit summary.
+    fn error(&mut self, text: impl Into<String>, now: Instant) {
+        self.show(Role::Error, text, now);
+        self.errors += 1;
+    }
+}
+
+/// A pending yes/no question and what a yes does.
+#[derive(Debug, Clone)]
+struct Confirm {
+    question: String,
+    action: Confirmable,
+}
+
+/// Actions that wait for the user's confirmation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum Confirmable {
+    ResetCounter,
+}
+
+impl Confirmable {
+    fn message(self) -> Message {
+        match self {
+            Confirmable::ResetCounter => Message::Reset,
         }
     }
 }
@@ -38,6 +309,90 @@ enum Message {
     Increment,
     Decrement,
     Reset,
+    /// Answer the pending question.
+    Confirm(bool),
+    FocusNext,
+    RefreshFocused,
+    Tick(Instant),
+    /// Clear and re-initialize the terminal. Handled by the loop, not `update`.
+    ResyncTerminal,
+    /// Hand the terminal to an external program. Handled by the loop.
+    OpenExternal(Program),
+    CounterEdited(String),
+    ExternalFailed(String),
+    /// Start the n-th configured quick command. Handled by the loop.
+    RunQuickCommand(usize),
+    /// A quick command started as run number `run`. Output and exits of
+    /// earlier runs that arrive after this are dropped.
+    CommandStarted {
+        run: u64,
+        name: String,
+    },
+    CommandOutput {
+        run: u64,
+        line: String,
+    },
+    CommandExited {
+        run: u64,
+        name: String,
+        code: Option<i32>,
+    },
+    ScrollOutputUp,
+    ScrollOutputDown,
+    /// Switch the output pane between soft-wrapping and panning long lines.
+    ToggleOutputWrap,
+    /// Pan the output pane by the given number of columns.
+    PanOutput(isize),
+    /// Copy the focused pane's text to the clipboard. Handled by the loop.
+    Yank,
+    Copied(Copied),
+    /// Open the most recent URL in the output in a browser. Handled by the loop.
+    OpenLink,
+    ToggleShare,
+    /// Close whichever popup is open.
+    Dismiss,
+    /// Open the actions for a pane at a screen position.
+    OpenContextMenu {
+        pane: PaneId,
+        column: u16,
+        row: u16,
+    },
+    SelectMenuItem(isize),
+    /// Close the context menu and run its item at this index.
+    ChooseMenuItem(usize),
+    ToggleNotifications,
+    /// Move the notification selection by the given number of items.
+    SelectNotification(isize),
+    CycleNotificationFilter,
+    ShowNotificationDetails,
+    /// Run the selected notification's quick command again.
+    RetryNotification,
+    /// Run the quick command with the given name. Handled by the loop.
+    RerunQuickCommand(String),
+    /// Explore the output as a JSON tree.
+    OpenJsonTree,
+    /// Move the tree selection by the given number of nodes.
+    SelectJsonNode(isize),
+    ToggleJsonNode,
+    CopyJsonPath,
+    CopyJsonValue,
+    StartJsonSearch,
+    JsonSearchInput(char),
+    JsonSearchBackspace,
+    /// Search for the typed text, or the last search again.
+    JsonSearchNext,
+    /// Copy the given text to the clipboard. Handled by the loop.
+    CopyText(String),
+    OutputPrepared(Arc<PreparedOutput>),
+    ToggleTasks,
+    /// Switch between relative, local and UTC timestamps.
+    CycleTimeFormat,
+    /// Remember the time format for the next run. Handled by the loop.
+    SaveTimeFormat(TimeFormat),
+    /// The terminal was resized to the given columns and rows.
+    Resize(u16, u16),
+    FocusGained,
+    FocusLost,
     Quit,
 }
 
@@ -49,149 +404,1628 @@ enum Message {
 ///
 /// # Errors
 ///
-/// This function will return an error if .
-pub fn application_loop(api_key: [u8; API_KEY_LEN]) -> color_eyre::Result<()> {
+/// This function will return an error if the API key cannot be loaded, if
+/// `goto` does not describe a valid view, or if drawing to the terminal fails.
+pub fn application_loop(
+    load_api_key: impl FnOnce() -> color_eyre::Result<[u8; API_KEY_LEN]> + Send + 'static,
+    profile: &str,
+    config: Config,
+    goto: Option<DeepLink>,
+    mirror: Option<Mirror>,
+    capabilities: Option<Capabilities>,
+    tutorial: bool,
+) -> color_eyre::Result<Summary> {
+    let started = Instant::now();
     tui::install_panic_hook();
-    let mut terminal = tui::init_terminal()?;
-    let mut model = Model::new(api_key);
+    let mut terminal = tui::init_terminal(!config.disable_mouse)?;
+    // Probe before anything else reads input, as the answers arrive there
+    let capabilities = capabilities.unwrap_or_else(Capabilities::load_or_probe);
+    let mut events = TerminalEvents;
+
+    let model = wait_for_api_key(&mut terminal, &mut events, load_api_key).and_then(|api_key| {
+        let mut model = Model {
+            hyperlinks: capabilities.osc8,
+            osc52: config.osc52_clipboard && capabilities.osc52,
+            screen: Screen {
+                whats_new: whats_new::after_upgrade(),
+                ..Screen::default()
+            },
+            time_format: TimeFormat::load(),
+            tutorial: tutorial.then(|| Tutorial::new(&TUTORIAL)),
+            ..Model::new(api_key, &config)
+        };
+        if config.safe_mode {
+            let toast = Toast::new(
+                Role::Warning,
+                "Safe mode: default settings, nothing is saved".to_string(),
+                model.now,
+            );
+            model.notifications.push(&toast, None);
+            model.feedback.toast = Some(toast);
+        }
+        if let Some(mirror) = &mirror {
+            let text = format!("Mirroring at http://{}", mirror.addr());
+            let toast = Toast::new(Role::Info, text, model.now);
+            model.notifications.push(&toast, None);
+            model.feedback.toast = Some(toast);
+        }
+        let problems = keymap::lint(&config, &capabilities);
+        for problem in &problems {
+            let toast = Toast::new(Role::Warning, problem.clone(), model.now);
+            model.notifications.push(&toast, None);
+            model.feedback.toast = Some(toast);
+        }
+        if problems.len() > 1 {
+            model.feedback.toast = Some(Toast::new(
+                Role::Warning,
+                format!("{} key binding problems, see notifications", problems.len()),
+                model.now,
+            ));
+        }
+        match &goto {
+            Some(link) => model.with_deep_link(link),
+            None => Ok(model),
+        }
+    });
+    let result = model.and_then(|model| {
+        run(
+            &mut terminal,
+            &mut events,
+            model,
+            profile,
+            &config,
+            mirror.as_ref(),
+        )
+    });
+    tui::restore_terminal()?;
+    for task in tasks::outliving(SHUTDOWN_GRACE) {
+        eprintln!(
+            "Warning: background task {} was still running at exit, after {}s.",
+            task.name,
+            task.started.elapsed().as_secs()
+        );
+    }
+    result.map(|model| Summary {
+        duration: started.elapsed(),
+        messages: model.messages,
+        errors: model.feedback.errors,
+        alerts: model.counters.alerts,
+    })
+}
+
+/// Update and render `model` until it quits, returning its final state.
+///
+/// Terminal upkeep (raw mode, title, hyperlinks) only happens when `events`
+/// come from a real terminal, so tests can drive the loop with a
+/// [`TestBackend`] and scripted events.
+fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    events: &mut impl EventSource,
+    mut model: Model,
+    profile: &str,
+    config: &Config,
+    mirror: Option<&Mirror>,
+) -> color_eyre::Result<Model> {
+    let interactive = events.is_terminal();
+
+    let mut cache = RenderCache::default();
+
+    // Background work (e.g. quick commands) reports back through this channel
+    let (tx, rx) = mpsc::channel();
+    // and stops once the loop is left. Work feeding the output pane also
+    // stops when something else takes the pane over.
+    let app = CancelToken::default();
+    let _stop_background_work = app.cancel_on_drop();
+    let mut output_work = app.child();
+    // Numbers the quick command runs, so their output cannot mix
+    let mut runs = 0;
+
+    // Output is analyzed for drawing in the background as it changes
+    let preparer = {
+        let tx = tx.clone();
+        Preparer::spawn(move |prepared| {
+            let _ = tx.send(Message::OutputPrepared(Arc::new(prepared)));
+        })
+    };
+    let mut submitted_output = model.output.version;
+
+    let mut shown_title: Option<String> = None;
 
     while model.running_state != RunningState::Done {
+        // Something outside the app (an external command, a suspend/resume)
+        // may have reset the terminal behind our back
+        if interactive && !tui::is_raw_mode_enabled()? {
+            tui::resync_terminal(terminal, !config.disable_mouse)?;
+        }
+
         // Render the current view
-        terminal.draw(|f| view(&mut model, f))?;
+        let started = Instant::now();
+        let mut timings = FrameTimings::default();
+        let frame = terminal.draw(|f| view(&mut model, &mut cache, &mut timings, f))?;
+        model.frame_budget.record(started.elapsed(), timings);
+        if let Some(mirror) = mirror {
+            mirror.publish(render::to_html_fragment(frame.buffer));
+        }
+        if interactive && model.hyperlinks {
+            let visible: Vec<Link> = model
+                .links
+                .iter()
+                .filter(|link| is_visible(link, frame.buffer))
+                .cloned()
+                .collect();
+            tui::draw_hyperlinks(&visible)?;
+        }
+        if interactive && !config.disable_terminal_title {
+            let title = title(profile, &model);
+            if shown_title.as_ref() != Some(&title) {
+                tui::set_title(&title)?;
+                shown_title = Some(title);
+            }
+        }
 
-        // Handle events and map to a Message
-        let mut current_msg = handle_event(&model)?;
+        // Collect messages from background work, then handle events and map
+        // them to a Message
+        let mut messages: Vec<Message> = rx.try_iter().collect();
+        messages.extend(handle_event(&model, events)?);
 
-        // Process updates as long as they return a non-None message
-        while current_msg.is_some() {
-            // update the model and get the next message
-            let (new_model, next_msg) = update(&model, current_msg.unwrap());
-            model = new_model;
-            current_msg = next_msg;
+        for msg in messages {
+            // Process updates as long as they return a non-None message
+            let mut current_msg = Some(msg);
+            while let Some(msg) = current_msg {
+                model.messages += 1;
+                current_msg = match msg {
+                    Message::ResyncTerminal => {
+                        if interactive {
+                            tui::resync_terminal(terminal, !config.disable_mouse)?;
+                        }
+                        None
+                    }
+                    Message::OpenExternal(program) => {
+                        if interactive {
+                            tui::restore_terminal()?;
+                        }
+                        let next_msg = run_external(&model, program);
+                        if interactive {
+                            tui::resync_terminal(terminal, !config.disable_mouse)?;
+                        }
+                        next_msg
+                    }
+                    Message::RunQuickCommand(index) => {
+                        output_work.cancel();
+                        output_work = app.child();
+                        runs += 1;
+                        Some(run_quick_command(
+                            config,
+                            index,
+                            runs,
+                            output_work.clone(),
+                            tx.clone(),
+                        ))
+                    }
+                    Message::RerunQuickCommand(name) => {
+                        let index = config
+                            .quick_commands
+                            .iter()
+                            .position(|command| command.name == name);
+                        output_work.cancel();
+                        output_work = app.child();
+                        runs += 1;
+                        Some(match index {
+                            Some(index) => run_quick_command(
+                                config,
+                                index,
+                                runs,
+                                output_work.clone(),
+                                tx.clone(),
+                            ),
+                            None => Message::ExternalFailed(format!(
+                                "Quick command `{}` is no longer configured",
+                                name
+                            )),
+                        })
+                    }
+                    Message::OpenLink => latest_url(&model).and_then(|url| {
+                        open::that_detached(url).err().map(|err| {
+                            Message::ExternalFailed(format!("Could not open {}: {}", url, err))
+                        })
+                    }),
+                    Message::Yank => Some(Message::CopyText(focused_text(&model))),
+                    Message::SaveTimeFormat(format) => format.save().err().map(|err| {
+                        Message::ExternalFailed(format!("Could not save the time format: {}", err))
+                    }),
+                    Message::CopyText(text) => Some(match clipboard::copy(&text, model.osc52) {
+                        Ok(copied) => Message::Copied(copied),
+                        Err(err) => Message::ExternalFailed(err.to_string()),
+                    }),
+                    msg => {
+                        // update the model and get the next message
+                        let alerts = model.counters.alerts;
+                        let next_msg = update(&mut model, msg);
+                        let counters = &model.counters;
+                        let critical =
+                            counters.thresholds.evaluate(counters.value) == Level::Critical;
+                        if interactive && critical && model.counters.alerts > alerts {
+                            if let Err(err) = config.bell.ring() {
+                                let failed = format!("Could not sound the alert: {}", err);
+                                update(&mut model, Message::ExternalFailed(failed));
+                            }
+                        }
+                        next_msg
+                    }
+                };
+            }
+        }
+
+        if model.output.version != submitted_output {
+            preparer.submit(model.output.version, model.output.lines.clone());
+            submitted_output = model.output.version;
         }
     }
 
-    tui::restore_terminal()?;
-    Ok(())
+    if shown_title.is_some() {
+        tui::set_title("")?;
+    }
+    Ok(model)
 }
 
-fn view(model: &mut Model, frame: &mut Frame) {
-    let layout = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
-        .split(frame.area());
+/// Run `load` on a background thread, showing a spinner until it is done.
+///
+/// # Errors
+///
+/// This function will return an error if `load` fails, if it takes longer
+/// than [`KEYCHAIN_TIMEOUT`], or if the user cancels with `q` or Esc.
+fn wait_for_api_key<B: Backend>(
+    terminal: &mut Terminal<B>,
+    events: &mut impl EventSource,
+    load: impl FnOnce() -> color_eyre::Result<[u8; API_KEY_LEN]> + Send + 'static,
+) -> color_eyre::Result<[u8; API_KEY_LEN]> {
+    let (tx, rx) = mpsc::channel();
+    tasks::spawn("api key lookup", move || {
+        let _ = tx.send(load());
+    });
 
-    for i in 0..2 {
-        let block = Block::bordered().title(format!("Block {}", i));
+    let started = Instant::now();
+    loop {
+        match rx.try_recv() {
+            Ok(api_key) => return api_key,
+            Err(TryRecvError::Disconnected) => {
+                return Err(eyre!("Looking up the API key failed unexpectedly."))
+            }
+            Err(TryRecvError::Empty) => {}
+        }
+        if started.elapsed() > KEYCHAIN_TIMEOUT {
+            return Err(eyre!(
+                "Timed out after {}s waiting for the keychain. \
+                If it is locked, unlock it and try again.",
+                KEYCHAIN_TIMEOUT.as_secs()
+            ));
+        }
 
+        let spinner = SPINNER[(started.elapsed().as_millis() / 100) as usize % SPINNER.len()];
+        terminal.draw(|f| {
+            let text = format!("{} Waiting for the keychain… (q to cancel)", spinner);
+            f.render_widget(Paragraph::new(text).centered(), centered(f.area(), 60, 1));
+        })?;
+        if let Some(Event::Key(key)) = events.next(Duration::from_millis(100))? {
+            if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                return Err(eyre!("Cancelled while waiting for the keychain."));
+            }
+        }
+    }
+}
+
+/// Render the view described by `screen` once into an off-screen buffer.
+///
+/// # Errors
+///
+/// This function will return an error if `screen` does not describe a valid
+/// view.
+pub fn render_once(
+    api_key: [u8; API_KEY_LEN],
+    config: Config,
+    screen: &DeepLink,
+    width: u16,
+    height: u16,
+) -> color_eyre::Result<Buffer> {
+    let mut model = Model::new(api_key, &config).with_deep_link(screen)?;
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    terminal.draw(|f| {
+        view(
+            &mut model,
+            &mut RenderCache::default(),
+            &mut FrameTimings::default(),
+            f,
+        )
+    })?;
+    Ok(terminal.backend().buffer().clone())
+}
+
+/// Suspend the TUI for an external program and map its outcome to a Message.
+///
+/// The editor edits the counter value; the pager shows the focused pane.
+fn run_external(model: &Model, program: Program) -> Option<Message> {
+    let contents = match program {
+        Program::Editor => format!("{}\n", model.counters.value),
+        Program::Pager => focused_text(model),
+    };
+    match (program, external::run_on_temp_file(program, &contents)) {
+        (_, Err(err)) => Some(Message::ExternalFailed(err.to_string())),
+        (Program::Editor, Ok(edited)) => Some(Message::CounterEdited(edited)),
+        (Program::Pager, Ok(_)) => None,
+    }
+}
+
+/// Start a configured quick command whose output is sent back over `tx`
+/// until `cancel` is cancelled.
+fn run_quick_command(
+    config: &Config,
+    index: usize,
+    run: u64,
+    cancel: CancelToken,
+    tx: mpsc::Sender<Message>,
+) -> Message {
+    let Some(command) = config.quick_commands.get(index) else {
+        return Message::ExternalFailed(format!("No quick command configured for {}", index + 1));
+    };
+
+    let name = command.name.clone();
+    let spawned = quick_command::spawn(command, cancel, move |event| {
+        let msg = match event {
+            CommandEvent::Line(line) => Message::CommandOutput { run, line },
+            CommandEvent::Exited(code) => Message::CommandExited {
+                run,
+                name: name.clone(),
+                code,
+            },
+        };
+        // The loop may already be gone if the user quit meanwhile
+        let _ = tx.send(msg);
+    });
+
+    match spawned {
+        Ok(()) => Message::CommandStarted {
+            run,
+            name: command.name.clone(),
+        },
+        Err(err) => Message::ExternalFailed(format!("Could not start `{}`: {}", command.name, err)),
+    }
+}
+
+/// Terminal title summarizing the app state, e.g. `monika: work (2 alerts)`.
+///
+/// tmux picks this up as the pane title, so it can be shown in pane borders
+/// or the window list.
+fn title(profile: &str, model: &Model) -> String {
+    match model.counters.alerts {
+        0 => format!("monika: {}", profile),
+        1 => format!("monika: {} (1 alert)", profile),
+        n => format!("monika: {} ({} alerts)", profile, n),
+    }
+}
+
+/// Whether all of `link` still shows in `buffer`, i.e. no popup or toast
+/// was drawn over it.
+fn is_visible(link: &Link, buffer: &Buffer) -> bool {
+    let mut x = link.x;
+    link.url.chars().all(|c| {
+        let cell = buffer.cell((x, link.y));
+        x += c.width().unwrap_or(0) as u16;
+        cell.is_some_and(|cell| cell.symbol().starts_with(c))
+    })
+}
+
+/// The last URL printed to the output pane.
+fn latest_url(model: &Model) -> Option<&str> {
+    model
+        .output
+        .lines
+        .iter()
+        .rev()
+        .find_map(|line| links::find_urls(line).last().map(|(_, url)| *url))
+}
+
+/// The text of the focused pane, for copying or the pager.
+fn focused_text(model: &Model) -> String {
+    match model.screen.focused {
+        PaneId::Output => model.output.lines.join("\n"),
+        id => pane_text(model, &model.counters.panes[id]),
+    }
+}
+
+fn pane_text(model: &Model, pane: &Pane) -> String {
+    let rate = model
+        .counters
+        .trend
+        .per_minute()
+        .map_or_else(|| "-".to_string(), |rate| format!("{:+.1}/min", rate));
+    // Pane text is mirrored, exported, printed and copied, so the key is
+    // only ever shown masked
+    format!(
+        "Counter: {}, API_KEY: {}\nTrend: {} {} {}",
+        pane.counter,
+        credentials::mask(&String::from_utf8_lossy(&model.api_key)),
+        model.counters.trend.arrow(),
+        rate,
+        model.counters.trend.sparkline()
+    )
+}
+
+/// Draw `model`, timing each component into `timings`.
+fn view(model: &mut Model, cache: &mut RenderCache, timings: &mut FrameTimings, frame: &mut Frame) {
+    let layout = ScreenLayout::compute(frame.area(), !model.output.lines.is_empty());
+    let status_area = layout.area(PaneId::Status);
+    model.screen.layout = layout.clone();
+
+    timings.measure("counters", || view_counters(model, &layout, frame));
+
+    if !model.output.lines.is_empty() {
+        // Wrapping and highlighting a full output pane is the costliest
+        // part of a frame, so it is only redone when the output changes
+        let inputs = (
+            model.output.version,
+            model.output.view.clone(),
+            model
+                .output
+                .prepared
+                .as_ref()
+                .map(|prepared| prepared.version),
+            model.highlighting,
+            model.hyperlinks,
+            model.screen.focused == PaneId::Output && model.terminal_focused,
+        );
+        timings.measure("output", || {
+            cache.render(
+                "output",
+                inputs,
+                layout.area(PaneId::Output),
+                frame.buffer_mut(),
+                |area, buf| view_output(model, area, buf),
+            )
+        });
+    } else {
+        model.links.clear();
+    }
+
+    if model.screen.sharing {
+        let link = model.deep_link().to_string();
+        let qr = QrCode::new(&link);
+        // Room for the code plus the border and the link underneath
+        let (cols, rows) = qr.size().unwrap_or((30, 3));
+        let area = centered(frame.area(), cols + 2, rows + 3);
+        let block = Block::bordered()
+            .title("Share view")
+            .title_style(model.theme.style(Role::Accent))
+            .title_bottom(Line::from(link.as_str()).centered());
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        frame.render_widget(qr, inner);
+    }
+
+    if let Some(notes) = &model.screen.whats_new {
+        let area = centered(frame.area(), 70, 20);
+        frame.render_widget(Clear, area);
         frame.render_widget(
-            Paragraph::new(format!(
-                "Counter: {}, API_KEY: {:?}",
-                model.counter, model.api_key
-            ))
-            .block(block),
-            layout[i],
+            Paragraph::new(notes.as_str())
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::bordered()
+                        .title("What's new")
+                        .title_style(model.theme.style(Role::Accent))
+                        .title_bottom(Line::from("Esc to close").right_aligned()),
+                ),
+            area,
+        );
+    }
+
+    if let Some(tree) = &model.json_tree {
+        timings.measure("JSON tree", || view_json_tree(model, tree, frame));
+    }
+
+    if model.screen.show_tasks {
+        timings.measure("tasks", || view_tasks(model, frame));
+    }
+
+    if model.notifications.open {
+        timings.measure("notifications", || view_notifications(model, frame));
+    }
+
+    if let Some(tutorial) = &model.tutorial {
+        view_tutorial(model, tutorial, status_area, frame);
+    }
+
+    if let Some(menu) = &model.screen.context_menu {
+        view_context_menu(model, menu, frame);
+    }
+
+    if let Some(confirm) = &model.counters.confirm {
+        let area = centered(frame.area(), 50, 4);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(confirm.question.as_str())
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::bordered()
+                        .title("Confirm")
+                        .title_style(model.theme.style(Role::Warning))
+                        .title_bottom(Line::from("y: yes  n: no").right_aligned()),
+                ),
+            area,
+        );
+    }
+
+    if let Some(toast) = &model.feedback.toast {
+        frame.render_widget(
+            Paragraph::new(toast.text.as_str()).style(model.theme.style(toast.role)),
+            status_area,
+        );
+    }
+    if model.notifications.unread > 0 {
+        frame.render_widget(
+            Paragraph::new(format!("n: {} new", model.notifications.unread))
+                .style(model.theme.style(Role::Accent))
+                .right_aligned(),
+            status_area,
         );
     }
 }
 
+fn view_context_menu(model: &Model, menu: &ContextMenu, frame: &mut Frame) {
+    let lines: Vec<Line> = menu
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let line = Line::from(format!(" {} ", item.label()));
+            if i == menu.selected {
+                line.style(
+                    model
+                        .theme
+                        .style(Role::Accent)
+                        .add_modifier(Modifier::REVERSED),
+                )
+            } else {
+                line
+            }
+        })
+        .collect();
+    frame.render_widget(Clear, menu.area);
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered()), menu.area);
+}
+
+/// The current tutorial step, just above the status bar.
+fn view_tutorial(model: &Model, tutorial: &Tutorial<Message>, status: Rect, frame: &mut Frame) {
+    let Some(step) = tutorial.step() else {
+        return;
+    };
+    let (current, total) = tutorial.progress();
+    let width = (step.text.len() as u16 + 4).min(frame.area().width);
+    let area = Rect {
+        y: status.y.saturating_sub(3),
+        height: 3,
+        ..centered(frame.area(), width, 3)
+    };
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(step.text).centered().block(
+            Block::bordered()
+                .title(format!("Tutorial {}/{}", current, total))
+                .title_style(model.theme.style(Role::Accent))
+                .border_style(model.theme.style(Role::Warning)),
+        ),
+        area,
+    );
+}
+
+/// The counter panes, highlighting the focused one and the tutorial's target.
+fn view_counters(model: &Model, layout: &ScreenLayout, frame: &mut Frame) {
+    for (id, pane) in model.counters.panes.iter() {
+        let updated = model
+            .time_format
+            .format(model.now.duration_since(pane.last_refreshed));
+        let mut block = Block::bordered()
+            .title(format!("Block {}", id.index().unwrap_or(0)))
+            .title_style(model.theme.style(Role::Accent))
+            .title_bottom(Line::from(format!("updated {}", updated)).right_aligned());
+        if id == model.screen.focused && model.terminal_focused {
+            block = focus_style(model, block);
+        }
+        let step = model.tutorial.as_ref().and_then(Tutorial::step);
+        if step.is_some_and(|step| step.target == Some(id)) {
+            block = block.border_style(model.theme.style(Role::Warning));
+        }
+
+        frame.render_widget(
+            Paragraph::new(pane_text(model, pane))
+                .style(
+                    model
+                        .theme
+                        .style(model.counters.thresholds.evaluate(pane.counter).role()),
+                )
+                .block(block),
+            layout.area(id),
+        );
+    }
+}
+
+/// Mark the focused pane by its border's weight as well as its color, so
+/// focus shows with any theme and without color.
+fn focus_style<'a>(model: &Model, block: Block<'a>) -> Block<'a> {
+    block
+        .border_type(BorderType::Thick)
+        .border_style(model.theme.style(Role::Accent))
+}
+
+/// The output pane, recording where URLs landed in `model.links`.
+fn view_output(model: &mut Model, area: Rect, buf: &mut Buffer) {
+    let prepared = model
+        .output
+        .prepared
+        .as_deref()
+        .filter(|prepared| prepared.version == model.output.version);
+    // Keep the tail in view unless the user scrolled up
+    let rows = model.output.view.rows(
+        &model.output.lines,
+        usize::from(area.width.saturating_sub(2)),
+        usize::from(area.height.saturating_sub(2)),
+    );
+    let lines: Vec<Line> = rows
+        .iter()
+        .map(|row| {
+            // Until the current output is prepared it is drawn plain
+            let tokens = prepared
+                .filter(|prepared| model.highlighting && prepared.is_json)
+                .and_then(|prepared| prepared.tokens.get(row.index));
+            match tokens {
+                Some(tokens) => {
+                    highlight::styled(row.line, row.range.clone(), tokens, &model.theme)
+                }
+                None => Line::raw(row.text()),
+            }
+        })
+        .collect();
+
+    // Remember where URLs landed that are fully visible on one row, to
+    // turn them into links
+    model.links = rows
+        .iter()
+        .zip(area.y + 1..)
+        .flat_map(|(row, y)| {
+            links::find_urls(row.line)
+                .into_iter()
+                .filter(|(start, url)| {
+                    row.range.contains(start) && start + url.len() <= row.range.end
+                })
+                .map(move |(start, url)| Link {
+                    x: area.x + 1 + Line::raw(&row.line[row.range.start..start]).width() as u16,
+                    y,
+                    url: url.to_string(),
+                })
+        })
+        .collect();
+
+    let mut block = Block::bordered()
+        .title(if model.output.view.wrap {
+            "Output (wrapped)".to_string()
+        } else if model.output.view.column > 0 {
+            format!("Output (column {})", model.output.view.column + 1)
+        } else {
+            "Output".to_string()
+        })
+        .title_style(model.theme.style(Role::Accent));
+    if model.screen.focused == PaneId::Output && model.terminal_focused {
+        block = focus_style(model, block);
+    }
+    if !model.hyperlinks && latest_url(model).is_some() {
+        block = block.title_bottom(Line::from("o: open link").right_aligned());
+    }
+    Paragraph::new(lines).block(block).render(area, buf);
+}
+
+fn view_json_tree(model: &Model, tree: &JsonTree, frame: &mut Frame) {
+    let area = frame.area();
+    let area = centered(
+        area,
+        area.width.saturating_sub(4),
+        area.height.saturating_sub(4),
+    );
+    let nodes = tree.nodes();
+    let lines: Vec<Line> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| {
+            let marker = match (node.expandable, node.expanded) {
+                (false, _) => "  ",
+                (true, false) => "▸ ",
+                (true, true) => "▾ ",
+            };
+            let label = if node.label.is_empty() {
+                String::new()
+            } else {
+                format!("{}: ", node.label)
+            };
+            let prefix = format!("{}{}", "  ".repeat(node.depth), marker);
+            let line = if model.highlighting {
+                let mut spans = vec![
+                    Span::raw(prefix),
+                    Span::styled(label, model.theme.style(Role::Info)),
+                ];
+                spans.extend(
+                    highlight::json_line(&node.summary, 0..node.summary.len(), &model.theme).spans,
+                );
+                Line::from(spans)
+            } else {
+                Line::raw(format!("{}{}{}", prefix, label, node.summary))
+            };
+            if i == tree.selected {
+                line.patch_style(Modifier::REVERSED)
+            } else {
+                line
+            }
+        })
+        .collect();
+    let hints = match &tree.search {
+        Some(query) => format!("/{}▏", query),
+        None => {
+            "Enter: expand  /: search  n: next  p: copy path  y: copy value  Esc: close".to_string()
+        }
+    };
+    // Keep the selection in view
+    let visible = usize::from(area.height.saturating_sub(2));
+    let top = tree.selected.saturating_sub(visible.saturating_sub(1));
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).scroll((top as u16, 0)).block(
+            Block::bordered()
+                .title("Output as JSON")
+                .title_style(model.theme.style(Role::Accent))
+                .title_bottom(Line::from(hints).right_aligned()),
+        ),
+        area,
+    );
+}
+
+/// Background tasks, running ones first.
+fn view_tasks(model: &Model, frame: &mut Frame) {
+    let mut tasks = tasks::list();
+    tasks.sort_by_key(|task| (task.state != State::Running, task.started));
+    let budget_role = match model.frame_budget.slow_frames {
+        0 => Role::Info,
+        _ => Role::Warning,
+    };
+    let budget = Line::styled(
+        format!("Frames: {}", model.frame_budget.describe()),
+        model.theme.style(budget_role),
+    );
+    let lines: Vec<Line> = std::iter::once(budget)
+        .chain(tasks.iter().map(|task| {
+            let (state, role) = match task.state {
+                State::Running => ("running", Role::Info),
+                State::Finished => ("finished", Role::Success),
+                State::Panicked => ("panicked", Role::Error),
+            };
+            let ran = task
+                .ended
+                .unwrap_or(model.now)
+                .saturating_duration_since(task.started);
+            let kind = if task.service { " (service)" } else { "" };
+            Line::styled(
+                format!(
+                    "{:<9} {:>6.1}s  {}{}",
+                    state,
+                    ran.as_secs_f32(),
+                    task.name,
+                    kind
+                ),
+                model.theme.style(role),
+            )
+        }))
+        .collect();
+    let area = centered(frame.area(), 70, 16);
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::bordered()
+                .title("Background tasks")
+                .title_style(model.theme.style(Role::Accent))
+                .title_bottom(Line::from("Esc to close").right_aligned()),
+        ),
+        area,
+    );
+}
+
+/// The notification center, with the selected notification's details on top
+/// if asked for.
+fn view_notifications(model: &Model, frame: &mut Frame) {
+    let center = &model.notifications;
+    let area = centered(frame.area(), 70, 16);
+    let lines: Vec<Line> = center
+        .visible()
+        .iter()
+        .enumerate()
+        .map(|(i, notification)| {
+            let age = model
+                .time_format
+                .format(model.now.duration_since(notification.at));
+            let mut style = model.theme.style(notification.role);
+            if i == center.selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            Line::styled(format!("{:>9}  {}", age, notification.text), style)
+        })
+        .collect();
+    // Keep the selection in view
+    let visible = usize::from(area.height.saturating_sub(2));
+    let top = center.selected.saturating_sub(visible.saturating_sub(1));
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).scroll((top as u16, 0)).block(
+            Block::bordered()
+                .title(format!("Notifications ({})", center.filter.label()))
+                .title_style(model.theme.style(Role::Accent))
+                .title_bottom(
+                    Line::from("f: filter  Enter: details  r: retry  Esc: close").right_aligned(),
+                ),
+        ),
+        area,
+    );
+
+    let Some(notification) = center.selected().filter(|_| center.details) else {
+        return;
+    };
+    let area = centered(frame.area(), 60, 8);
+    let mut block = Block::bordered()
+        .title(format!("{:?}", notification.role))
+        .title_style(model.theme.style(notification.role))
+        .title_bottom(
+            Line::from(
+                model
+                    .time_format
+                    .format(model.now.duration_since(notification.at)),
+            )
+            .left_aligned(),
+        );
+    if notification.retry.is_some() {
+        block = block.title_bottom(Line::from("r: retry").right_aligned());
+    }
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(notification.text.as_str())
+            .wrap(Wrap { trim: false })
+            .block(block),
+        area,
+    );
+}
+
+/// A `width` by `height` rectangle in the middle of `area`, shrunk to fit.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let (width, height) = (width.min(area.width), height.min(area.height));
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
 /// Convert Event to Message
 ///
-/// We don't need to pass in a `model` to this function in this example
-/// but you might need it as your project evolves
-fn handle_event(_: &Model) -> color_eyre::Result<Option<Message>> {
-    if event::poll(Duration::from_millis(250))? {
-        if let Event::Key(key) = event::read()? {
-            if key.kind == event::KeyEventKind::Press {
-                return Ok(handle_key(key));
-            }
+/// A `Tick` drives the per-pane refresh schedule. It is emitted when no event
+/// arrives within the poll timeout, and also along with an event once a tick
+/// is overdue, so steady input such as mouse motion cannot hold it off. Ticks
+/// slow down while the terminal is unfocused.
+fn handle_event(model: &Model, events: &mut impl EventSource) -> color_eyre::Result<Vec<Message>> {
+    let rate = if model.terminal_focused {
+        TICK_RATE
+    } else {
+        BACKGROUND_TICK_RATE
+    };
+    let due = model.now + rate;
+    let event = events.next(due.saturating_duration_since(Instant::now()))?;
+    let now = Instant::now();
+    let tick = event.is_none() || now >= due;
+    let msg = match event {
+        Some(Event::Key(key)) if key.kind == event::KeyEventKind::Press => handle_key(model, key),
+        Some(Event::Mouse(mouse)) => handle_mouse(model, mouse),
+        Some(Event::Resize(columns, rows)) => Some(Message::Resize(columns, rows)),
+        Some(Event::FocusGained) => Some(Message::FocusGained),
+        Some(Event::FocusLost) => Some(Message::FocusLost),
+        Some(_) | None => None,
+    };
+    Ok(msg
+        .into_iter()
+        .chain(tick.then_some(Message::Tick(now)))
+        .collect())
+}
+
+/// Right-click opens a pane's context menu, left-click picks from it.
+fn handle_mouse(model: &Model, mouse: event::MouseEvent) -> Option<Message> {
+    let (column, row) = (mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Right) => {
+            let pane = model.screen.layout.pane_at(column, row)?;
+            Some(Message::OpenContextMenu { pane, column, row })
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            let menu = model.screen.context_menu.as_ref()?;
+            Some(match menu.item_at(column, row) {
+                Some(index) => Message::ChooseMenuItem(index),
+                None => Message::Dismiss,
+            })
         }
+        _ => None,
     }
-    Ok(None)
 }
 
-fn handle_key(key: event::KeyEvent) -> Option<Message> {
-    match key.code {
+fn handle_key(model: &Model, key: event::KeyEvent) -> Option<Message> {
+    if model.counters.confirm.is_some() {
+        return match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => Some(Message::Confirm(true)),
+            KeyCode::Char('n') | KeyCode::Esc => Some(Message::Confirm(false)),
+            _ => None,
+        };
+    }
+    if let Some(menu) = &model.screen.context_menu {
+        return match key.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Message::SelectMenuItem(1)),
+            KeyCode::Char('k') | KeyCode::Up => Some(Message::SelectMenuItem(-1)),
+            KeyCode::Enter => Some(Message::ChooseMenuItem(menu.selected)),
+            KeyCode::Esc | KeyCode::Char('m') => Some(Message::Dismiss),
+            KeyCode::Char('q') => Some(Message::Quit),
+            _ => None,
+        };
+    }
+    if model.notifications.open {
+        return match key.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Message::SelectNotification(1)),
+            KeyCode::Char('k') | KeyCode::Up => Some(Message::SelectNotification(-1)),
+            KeyCode::Char('f') => Some(Message::CycleNotificationFilter),
+            KeyCode::Char('r') => Some(Message::RetryNotification),
+            KeyCode::Enter => Some(Message::ShowNotificationDetails),
+            KeyCode::Char('n') => Some(Message::ToggleNotifications),
+            KeyCode::Esc => Some(Message::Dismiss),
+            KeyCode::Char('q') => Some(Message::Quit),
+            _ => None,
+        };
+    }
+    if let Some(tree) = &model.json_tree {
+        if tree.search.is_some() {
+            return match key.code {
+                KeyCode::Char(c) => Some(Message::JsonSearchInput(c)),
+                KeyCode::Backspace => Some(Message::JsonSearchBackspace),
+                KeyCode::Enter => Some(Message::JsonSearchNext),
+                KeyCode::Esc => Some(Message::Dismiss),
+                _ => None,
+            };
+        }
+        return match key.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Message::SelectJsonNode(1)),
+            KeyCode::Char('k') | KeyCode::Up => Some(Message::SelectJsonNode(-1)),
+            KeyCode::PageDown => Some(Message::SelectJsonNode(10)),
+            KeyCode::PageUp => Some(Message::SelectJsonNode(-10)),
+            KeyCode::Enter | KeyCode::Char(' ') => Some(Message::ToggleJsonNode),
+            KeyCode::Char('p') => Some(Message::CopyJsonPath),
+            KeyCode::Char('y') => Some(Message::CopyJsonValue),
+            KeyCode::Char('/') => Some(Message::StartJsonSearch),
+            KeyCode::Char('n') => Some(Message::JsonSearchNext),
+            KeyCode::Esc => Some(Message::Dismiss),
+            KeyCode::Char('q') => Some(Message::Quit),
+            _ => None,
+        };
+    }
+    let builtin = match key.code {
+        KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ResyncTerminal)
+        }
         KeyCode::Char('j') => Some(Message::Increment),
         KeyCode::Char('k') => Some(Message::Decrement),
+        KeyCode::Char('r') => Some(Message::RefreshFocused),
+        KeyCode::Char('e') => Some(Message::OpenExternal(Program::Editor)),
+        KeyCode::Char('v') => Some(Message::OpenExternal(Program::Pager)),
+        KeyCode::Char('y') => Some(Message::Yank),
+        KeyCode::Char('o') => Some(Message::OpenLink),
+        KeyCode::Char('s') => Some(Message::ToggleShare),
+        KeyCode::Char('n') => Some(Message::ToggleNotifications),
+        KeyCode::Esc => Some(Message::Dismiss),
+        KeyCode::Tab => Some(Message::FocusNext),
+        KeyCode::PageUp => Some(Message::ScrollOutputUp),
+        KeyCode::PageDown => Some(Message::ScrollOutputDown),
+        KeyCode::Char('w') => Some(Message::ToggleOutputWrap),
+        KeyCode::Char('t') => Some(Message::OpenJsonTree),
+        KeyCode::Char('D') => Some(Message::ToggleTasks),
+        KeyCode::Char('T') => Some(Message::CycleTimeFormat),
+        KeyCode::Char('m') => {
+            let area = model.screen.layout.area(model.screen.focused);
+            Some(Message::OpenContextMenu {
+                pane: model.screen.focused,
+                column: area.x + 2,
+                row: area.y + 1,
+            })
+        }
+        KeyCode::Char('h') | KeyCode::Left => Some(Message::PanOutput(-(PAN_COLUMNS as isize))),
+        KeyCode::Char('l') | KeyCode::Right => Some(Message::PanOutput(PAN_COLUMNS as isize)),
+        KeyCode::Char(digit @ '1'..='9') => Some(Message::RunQuickCommand(
+            digit.to_digit(10).expect("matched a digit") as usize - 1,
+        )),
         KeyCode::Char('q') => Some(Message::Quit),
         _ => None,
+    };
+    builtin.or_else(|| {
+        let chord = Chord::from(key);
+        model
+            .quick_keys
+            .iter()
+            .find(|(bound, _)| *bound == chord)
+            .map(|(_, name)| Message::RerunQuickCommand(name.clone()))
+    })
+}
+
+/// Apply `msg`, keeping any toast it raises in the notification center.
+fn update(model: &mut Model, msg: Message) -> Option<Message> {
+    // A failed quick command can be retried from the notification center
+    let retry = match &msg {
+        Message::CommandExited { name, code, .. } if *code != Some(0) => Some(name.clone()),
+        _ => None,
+    };
+    if let Some(tutorial) = &mut model.tutorial {
+        tutorial.observe(&msg);
+    }
+    let shown = model.feedback.toast.clone();
+    let next_msg = apply(model, msg);
+    if let Some(toast) = &model.feedback.toast {
+        let is_new = shown
+            .as_ref()
+            .is_none_or(|old| old.shown_at != toast.shown_at || old.text != toast.text);
+        if is_new {
+            model.notifications.push(toast, retry);
+        }
+    }
+    next_msg
+}
+
+/// Pass `msg` to each domain's reducer until one handles it. Reducers only
+/// change their own part of the model, and report through `feedback`.
+fn apply(model: &mut Model, msg: Message) -> Option<Message> {
+    let now = model.now;
+    let feedback = &mut model.feedback;
+    let with_output = !model.output.lines.is_empty();
+    let reduced = counters::reduce(&mut model.counters, feedback, now, msg)
+        .or_else(|msg| output::reduce(&mut model.output, feedback, now, msg))
+        .or_else(|msg| {
+            let lines = &model.output.lines;
+            json::reduce(&mut model.json_tree, lines, feedback, now, msg)
+        })
+        .or_else(|msg| notifications::reduce(&mut model.notifications, msg))
+        .or_else(|msg| navigation::reduce(&mut model.screen, with_output, msg));
+    match reduced {
+        Ok(next_msg) => next_msg,
+        Err(msg) => apply_to_all(model, msg),
     }
 }
 
-fn update(model: &Model, msg: Message) -> (Model, Option<Message>) {
+/// Handle the messages that concern more than one domain.
+fn apply_to_all(model: &mut Model, msg: Message) -> Option<Message> {
     match msg {
-        Message::Increment => {
-            let new_model = Model {
-                counter: model.counter + 1,
-                ..*model
-            };
-            if model.counter > 50 {
-                return (new_model, Some(Message::Reset));
-            }
-            return (new_model, None);
+        Message::Tick(now) => {
+            model.now = now;
+            // Automatic refreshes wait until the user looks again
+            model.counters.tick(now, !model.terminal_focused);
+            let toast = &mut model.feedback.toast;
+            *toast = toast.take().filter(|toast| !toast.is_expired(now));
+            None
         }
-        Message::Decrement => {
-            let new_model = Model {
-                counter: model.counter - 1,
-                ..*model
-            };
-            if model.counter < -50 {
-                return (new_model, Some(Message::Reset));
+        Message::RefreshFocused => {
+            model.counters.refresh(model.screen.focused, model.now);
+            None
+        }
+        Message::Dismiss => {
+            // Popups close from the top down
+            let screen = &mut model.screen;
+            if screen.context_menu.is_some() {
+                screen.context_menu = None;
+            } else if screen.whats_new.is_some() {
+                screen.whats_new = None;
+            } else if model.notifications.details {
+                model.notifications.details = false;
+            } else if model.notifications.open {
+                model.notifications.toggle();
+            } else if let Some(tree) = model.json_tree.as_mut().filter(|t| t.search.is_some()) {
+                tree.search = None;
+            } else if model.json_tree.is_some() {
+                model.json_tree = None;
+            } else if screen.show_tasks {
+                screen.show_tasks = false;
+            } else {
+                screen.sharing = false;
             }
-            return (new_model, None);
+            None
         }
-        Message::Reset => {
-            let new_model = Model {
-                counter: 0,
-                ..*model
-            };
-            return (new_model, None);
+        Message::CycleTimeFormat => {
+            model.time_format = model.time_format.next();
+            let text = format!("Timestamps: {}", model.time_format.label());
+            model.feedback.show(Role::Info, text, model.now);
+            Some(Message::SaveTimeFormat(model.time_format))
+        }
+        Message::FocusGained => {
+            model.terminal_focused = true;
+            // Catch up on paused refreshes right away
+            Some(Message::Tick(Instant::now()))
+        }
+        Message::FocusLost => {
+            model.terminal_focused = false;
+            None
         }
         Message::Quit => {
             // You can handle cleanup and exit here
-            let new_model = Model {
-                running_state: RunningState::Done,
-                ..*model
-            };
-            return (new_model, None);
+            model.running_state = RunningState::Done;
+            None
         }
-    };
+        // Every draw lays out for the current size, so there is nothing to
+        // update yet
+        Message::Resize(..) => None,
+        // Side effects are carried out by `run` and leave the model as it is,
+        // and anything else was already handled by its domain
+        _ => None,
+    }
 }
 
 mod tui {
     use ratatui::{
         backend::{Backend, CrosstermBackend},
         crossterm::{
+            cursor::MoveTo,
+            event::{
+                DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+            },
+            style::Print,
             terminal::{
                 disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+                SetTitle,
             },
-            ExecutableCommand,
+            ExecutableCommand, QueueableCommand,
         },
         Terminal,
     };
-    use std::{io::stdout, panic};
+    use std::{
+        io::{stdout, Write},
+        panic,
+    };
 
-    pub fn init_terminal() -> color_eyre::Result<Terminal<impl Backend>> {
+    use crate::links::{self, Link};
+
+    pub fn init_terminal(mouse: bool) -> color_eyre::Result<Terminal<impl Backend>> {
         enable_raw_mode()?;
-        stdout().execute(EnterAlternateScreen)?;
+        stdout()
+            .execute(EnterAlternateScreen)?
+            .execute(EnableFocusChange)?;
+        if mouse {
+            stdout().execute(EnableMouseCapture)?;
+        }
         let terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
         Ok(terminal)
     }
 
     pub fn restore_terminal() -> color_eyre::Result<()> {
-        stdout().execute(LeaveAlternateScreen)?;
+        stdout()
+            .execute(DisableMouseCapture)?
+            .execute(DisableFocusChange)?
+            .execute(LeaveAlternateScreen)?;
+        disable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Set the terminal (and tmux pane) title; an empty title clears it.
+    pub fn set_title(title: &str) -> color_eyre::Result<()> {
+        stdout().execute(SetTitle(title))?;
+        Ok(())
+    }
+
+    /// Redraw each link's text over itself as an OSC 8 hyperlink.
+    ///
+    /// Done after the frame is drawn, since escapes inside buffer cells would
+    /// throw off ratatui's width accounting.
+    pub fn draw_hyperlinks(links: &[Link]) -> color_eyre::Result<()> {
+        let mut stdout = stdout();
+        for link in links {
+            stdout
+                .queue(MoveTo(link.x, link.y))?
+                .queue(Print(links::osc8(&link.url, &link.url)))?;
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /// Whether the terminal really is in raw mode. crossterm only remembers
+    /// whether it enabled raw mode, so the tty itself is asked.
+    #[cfg(unix)]
+    pub fn is_raw_mode_enabled() -> color_eyre::Result<bool> {
+        use std::{fs::File, os::fd::AsRawFd};
+
+        let tty = File::open("/dev/tty")?;
+        Ok(is_raw(tty.as_raw_fd())?)
+    }
+
+    #[cfg(not(unix))]
+    pub fn is_raw_mode_enabled() -> color_eyre::Result<bool> {
+        Ok(ratatui::crossterm::terminal::is_raw_mode_enabled()?)
+    }
+
+    /// Whether the terminal `fd` reads input unbuffered and unechoed, which
+    /// is what raw mode is about.
+    #[cfg(unix)]
+    pub fn is_raw(fd: std::os::fd::RawFd) -> std::io::Result<bool> {
+        // SAFETY: `termios` is only read after `tcgetattr` filled it in
+        let mut termios = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(fd, &mut termios) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(termios.c_lflag & (libc::ICANON | libc::ECHO) == 0)
+    }
+
+    /// Bring a disturbed terminal back into the state the TUI expects.
+    ///
+    /// Re-enters raw mode and the alternate screen, then clears so the next
+    /// draw repaints every cell instead of diffing against stale contents.
+    pub fn resync_terminal<B: Backend>(
+        terminal: &mut Terminal<B>,
+        mouse: bool,
+    ) -> color_eyre::Result<()> {
+        // Enabling alone does nothing while crossterm believes raw mode is
+        // still on
         disable_raw_mode()?;
+        enable_raw_mode()?;
+        stdout()
+            .execute(EnterAlternateScreen)?
+            .execute(EnableFocusChange)?;
+        if mouse {
+            stdout().execute(EnableMouseCapture)?;
+        }
+        terminal.clear()?;
         Ok(())
     }
 
     pub fn install_panic_hook() {
         let original_hook = panic::take_hook();
         panic::set_hook(Box::new(move |panic_info| {
-            stdout().execute(LeaveAlternateScreen).unwrap();
+            stdout()
+                .execute(DisableMouseCapture)
+                .unwrap()
+                .execute(DisableFocusChange)
+                .unwrap()
+                .execute(LeaveAlternateScreen)
+                .unwrap();
             disable_raw_mode().unwrap();
             original_hook(panic_info);
         }));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context_menu::MenuAction,
+        input::Scripted,
+        pane::Panes,
+        rules::{Bound, BoundPolicy},
+    };
+
+    #[test]
+    fn scripted_keys_drive_the_loop() {
+        let config = Config::default();
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let mut events = Scripted::keys("jjjkq");
+
+        let model = run(
+            &mut terminal,
+            &mut events,
+            Model::new([0; API_KEY_LEN], &config),
+            "default",
+            &config,
+            None,
+        )
+        .unwrap();
+        assert_eq!(model.counters.value, 2);
+        assert_eq!(model.running_state, RunningState::Done);
+        assert_eq!(model.messages, 5);
+    }
+
+    #[test]
+    fn every_pane_is_reachable_by_keyboard_with_visible_focus() {
+        use ratatui::crossterm::event::KeyEvent;
+
+        let config = Config::default();
+        let mut model = Model::new([0; API_KEY_LEN], &config);
+        model.output.lines = vec!["https://example.com".to_string()];
+        let layout = ScreenLayout::compute(Rect::new(0, 0, 80, 24), true);
+        let press = |code| Some(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+
+        for (tabs, expected) in [PaneId::Live, PaneId::Manual, PaneId::Output]
+            .into_iter()
+            .enumerate()
+        {
+            let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+            let script = (0..tabs).map(|_| press(KeyCode::Tab)).chain([
+                press(KeyCode::Char('m')),
+                None,
+                press(KeyCode::Char('q')),
+            ]);
+            let model = run(
+                &mut terminal,
+                &mut Scripted::new(script),
+                model.clone(),
+                "default",
+                &config,
+                None,
+            )
+            .unwrap();
+            assert_eq!(model.screen.focused, expected);
+
+            // Only the focused pane has a thick border
+            let buffer = terminal.backend().buffer();
+            for id in [PaneId::Live, PaneId::Manual, PaneId::Output] {
+                let corner = layout.area(id);
+                let symbol = buffer[(corner.x, corner.y)].symbol();
+                assert_eq!(symbol == "┏", id == expected, "{:?} corner {}", id, symbol);
+            }
+            // Its context menu opens without the mouse
+            let menu = model.screen.context_menu.expect("m opens the context menu");
+            assert_eq!(menu.items, MenuAction::for_pane(expected));
+        }
+    }
+
+    #[test]
+    fn steady_input_does_not_starve_the_refresh_schedule() {
+        use ratatui::crossterm::event::{KeyEvent, MouseEvent};
+
+        let config = Config::default();
+        let mut model = Model::new([0; API_KEY_LEN], &config);
+        // The live pane refreshes every second; its last refresh and the last
+        // tick were two seconds ago
+        let earlier = model.now - Duration::from_secs(2);
+        model.now = earlier;
+        model.counters.panes = Panes::new(earlier);
+        model.counters.value = 7;
+        let moved = Some(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 1,
+            row: 1,
+            modifiers: KeyModifiers::NONE,
+        }));
+        let quit = Some(Event::Key(KeyEvent::new(
+            KeyCode::Char('q'),
+            KeyModifiers::NONE,
+        )));
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let model = run(
+            &mut terminal,
+            &mut Scripted::new([moved.clone(), moved, quit]),
+            model,
+            "default",
+            &config,
+            None,
+        )
+        .unwrap();
+        assert_eq!(model.counters.panes[PaneId::Live].counter, 7);
+        assert!(model.counters.panes[PaneId::Live].last_refreshed > earlier);
+        assert_eq!(model.counters.panes[PaneId::Manual].counter, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn notices_a_terminal_taken_out_of_raw_mode() {
+        use std::{ffi::CStr, fs::OpenOptions, os::fd::AsRawFd};
+
+        // A pseudo-terminal stands in for the one the app runs in
+        let primary = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
+        assert!(primary >= 0);
+        assert_eq!(unsafe { libc::grantpt(primary) }, 0);
+        assert_eq!(unsafe { libc::unlockpt(primary) }, 0);
+        let mut name = [0; 128];
+        assert_eq!(
+            unsafe { libc::ptsname_r(primary, name.as_mut_ptr(), name.len()) },
+            0
+        );
+        let name = unsafe { CStr::from_ptr(name.as_ptr()) }.to_str().unwrap();
+        let tty = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(name)
+            .unwrap();
+        let fd = tty.as_raw_fd();
+
+        let mut termios = unsafe { std::mem::zeroed::<libc::termios>() };
+        assert_eq!(unsafe { libc::tcgetattr(fd, &mut termios) }, 0);
+        unsafe { libc::cfmakeraw(&mut termios) };
+        assert_eq!(unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) }, 0);
+        assert!(tui::is_raw(fd).unwrap());
+
+        // What a shell or an editor leaves behind when it exits
+        termios.c_lflag |= libc::ICANON | libc::ECHO;
+        assert_eq!(unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) }, 0);
+        assert!(!tui::is_raw(fd).unwrap());
+        unsafe { libc::close(primary) };
+    }
+
+    #[test]
+    fn pane_text_masks_the_api_key() {
+        let api_key = *b"sk_test_0123456789abcdefghijklmn";
+        let model = Model::new(api_key, &Config::default());
+        let text = pane_text(&model, &model.counters.panes[PaneId::Live]);
+        assert!(text.contains("API_KEY: sk_t…"));
+        assert!(!text.contains("0123456789"));
+    }
+
+    #[test]
+    fn exports_mask_the_api_key() {
+        let api_key = *b"sk_test_0123456789abcdefghijklmn";
+        let screen = "counter".parse::<DeepLink>().unwrap();
+        let buffer = render_once(api_key, Config::default(), &screen, 100, 30).unwrap();
+        for export in [
+            crate::render::to_html(&buffer),
+            crate::render::to_svg(&buffer),
+        ] {
+            assert!(export.contains("sk_t"));
+            assert!(!export.contains("0123456789"));
+        }
+    }
+
+    #[test]
+    fn links_under_popups_are_not_drawn() {
+        let link = Link {
+            x: 2,
+            y: 1,
+            url: "https://x.org".to_string(),
+        };
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 20, 3));
+        buffer.set_string(2, 1, "https://x.org", ratatui::style::Style::default());
+        assert!(is_visible(&link, &buffer));
+        buffer.set_string(8, 1, "│", ratatui::style::Style::default());
+        assert!(!is_visible(&link, &buffer));
+    }
+
+    #[test]
+    fn failed_commands_can_be_retried_from_notifications() {
+        let mut model = Model::new([0; API_KEY_LEN], &Config::default());
+        update(
+            &mut model,
+            Message::CommandExited {
+                run: 0,
+                name: "deploy".to_string(),
+                code: Some(1),
+            },
+        );
+        assert_eq!(model.notifications.unread, 1);
+
+        update(&mut model, Message::ToggleNotifications);
+        let next_msg = update(&mut model, Message::RetryNotification);
+        assert_eq!(
+            next_msg,
+            Some(Message::RerunQuickCommand("deploy".to_string()))
+        );
+    }
+
+    #[test]
+    fn output_of_a_cancelled_run_is_dropped() {
+        let mut model = Model::new([0; API_KEY_LEN], &Config::default());
+        let output = |run, line: &str| Message::CommandOutput {
+            run,
+            line: line.to_string(),
+        };
+        let started = |run| Message::CommandStarted {
+            run,
+            name: "logs".to_string(),
+        };
+        update(&mut model, started(1));
+        update(&mut model, output(1, "old"));
+
+        // Run 1 was cancelled, but some of its messages were still queued
+        update(&mut model, started(2));
+        update(&mut model, output(1, "stale"));
+        update(
+            &mut model,
+            Message::CommandExited {
+                run: 1,
+                name: "logs".to_string(),
+                code: None,
+            },
+        );
+        update(&mut model, output(2, "new"));
+        assert_eq!(model.output.lines, vec!["new"]);
+        let notified = model.notifications.visible();
+        assert!(notified.iter().all(|n| !n.text.contains("terminated")));
+    }
+
+    #[test]
+    fn asks_before_resetting_at_bound() {
+        let mut model = Model::new([0; API_KEY_LEN], &Config::default());
+        model.counters.value = 50;
+        let next_msg = update(&mut model, Message::Increment);
+        assert_eq!((model.counters.value, next_msg), (51, None));
+        assert!(model.counters.confirm.is_some());
+
+        // Further steps out of bounds do not ask again
+        update(&mut model, Message::Increment);
+        let next_msg = update(&mut model, Message::Confirm(true));
+        assert!(model.counters.confirm.is_none());
+        assert_eq!(next_msg, Some(Message::Reset));
+
+        model.counters.value = -50;
+        model.counters.bound = Bound {
+            policy: BoundPolicy::Clamp,
+            ..Bound::default()
+        };
+        update(&mut model, Message::Decrement);
+        assert_eq!(model.counters.value, -50);
+    }
+
+    #[test]
+    fn edits_are_bounded_like_steps() {
+        let mut model = Model::new([0; API_KEY_LEN], &Config::default());
+        update(&mut model, Message::CounterEdited(i32::MAX.to_string()));
+        assert_eq!(model.counters.value, i32::MAX);
+        assert!(model.counters.confirm.is_some());
+
+        // Stepping past the edge stays there rather than overflowing
+        update(&mut model, Message::Increment);
+        assert_eq!(model.counters.value, i32::MAX);
+    }
+
+    #[test]
+    fn context_menu_runs_chosen_action() {
+        let mut model = Model::new([0; API_KEY_LEN], &Config::default());
+        model.screen.layout = ScreenLayout::compute(Rect::new(0, 0, 80, 24), true);
+        let output = model.screen.layout.area(PaneId::Output);
+        let open = Message::OpenContextMenu {
+            pane: PaneId::Output,
+            column: output.x + 1,
+            row: output.y + 1,
+        };
+        update(&mut model, open);
+        update(&mut model, Message::SelectMenuItem(1));
+        let menu = model.screen.context_menu.clone().unwrap();
+        assert_eq!(menu.items[menu.selected], MenuAction::ToggleWrap);
+
+        let next_msg = update(&mut model, Message::ChooseMenuItem(menu.selected));
+        assert!(model.screen.context_menu.is_none());
+        assert_eq!(next_msg, Some(Message::ToggleOutputWrap));
+    }
+
+    #[test]
+    fn tutorial_advances_on_expected_messages() {
+        let mut model = Model {
+            tutorial: Some(Tutorial::new(&TUTORIAL)),
+            ..Model::new([0; API_KEY_LEN], &Config::default())
+        };
+        update(&mut model, Message::Decrement);
+        assert_eq!(model.tutorial.unwrap().progress().0, 1);
+        update(&mut model, Message::Increment);
+        let step = model.tutorial.as_ref().and_then(Tutorial::step).unwrap();
+        assert_eq!(step.text, TUTORIAL[1].text);
+    }
+
+    #[test]
+    fn summarizes_session() {
+        let summary = Summary {
+            duration: Duration::from_secs(3725),
+            messages: 40,
+            errors: 1,
+            alerts: 0,
+        };
+        assert_eq!(
+            summary.to_string(),
+            "Session lasted 1h 2m 5s\n  Messages processed: 40\n  Errors: 1\n  Threshold alerts: 0"
+        );
+    }
+
+    #[test]
+    fn unfinished_script_fails() {
+        let config = Config::default();
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let mut events = Scripted::keys("j");
+
+        let model = Model::new([0; API_KEY_LEN], &config);
+        assert!(run(&mut terminal, &mut events, model, "default", &config, None).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use std::time::Instant;
+
+use crate::{
+    config::Config,
+    pane::{PaneId, Panes},
+    rules::{Bound, BoundPolicy, Thresholds},
+    theme::Role,
+    toast::Toast,
+    trend::Trend,
+};
+
+use super::{Confirm, Confirmable, Feedback, Message};
+
+/// The counter, the panes showing it and what watches over its value.
+#[derive(Debug, Clone)]
+pub(super) struct Counters {
+    pub(super) value: i32,
+    pub(super) panes: Panes,
+    pub(super) trend: Trend,
+    pub(super) thresholds: Thresholds,
+    pub(super) bound: Bound,
+    /// A question the user has to answer before anything else.
+    pub(super) confirm: Option<Confirm>,
+    /// Threshold alerts raised this session.
+    pub(super) alerts: usize,
+}
+
+impl Counters {
+    pub(super) fn new(config: &Config, now: Instant) -> Self {
+        Self {
+            value: 0,
+            panes: Panes::new(now),
+            trend: Trend::default(),
+            thresholds: config.counter,
+            bound: config.counter_bound,
+            confirm: None,
+            alerts: 0,
+        }
+    }
+
+    /// Show the current value in the pane `id`, if it is a counter pane.
+    pub(super) fn refresh(&mut self, id: PaneId, now: Instant) {
+        if id.index().is_some() {
+            self.panes[id] = self.panes[id].refreshed(self.value, now);
+        }
+    }
+
+    /// Record the value for the trend and refresh the panes that are due,
+    /// unless `paused`.
+    pub(super) fn tick(&mut self, now: Instant, paused: bool) {
+        let value = self.value;
+        self.panes = self.panes.map(|pane| {
+            if !paused && pane.is_due(now) {
+                pane.refreshed(value, now)
+            } else {
+                pane
+            }
+        });
+        self.trend.record(now, value);
+    }
+}
+
+/// Handle counter changes and the confirmation they may ask for.
+pub(super) fn reduce(
+    counters: &mut Counters,
+    feedback: &mut Feedback,
+    now: Instant,
+    msg: Message,
+) -> Result<Option<Message>, Message> {
+    let next_msg = match msg {
+        Message::Increment => {
+            step_counter(counters, feedback, now, counters.value.saturating_add(1))
+        }
+        Message::Decrement => {
+            step_counter(counters, feedback, now, counters.value.saturating_sub(1))
+        }
+        Message::Reset => {
+            counters.value = 0;
+            None
+        }
+        Message::Confirm(yes) => counters
+            .confirm
+            .take()
+            .filter(|_| yes)
+            .map(|confirm| confirm.action.message()),
+        Message::CounterEdited(text) => match text.trim().parse::<i32>() {
+            Ok(counter) => {
+                feedback.show(Role::Success, format!("Counter set to {}", counter), now);
+                // Edits are held to the same bounds and thresholds as steps
+                step_counter(counters, feedback, now, counter)
+            }
+            Err(_) => {
+                feedback.error(format!("Not a number: `{}`", text.trim()), now);
+                None
+            }
+        },
+        msg => return Err(msg),
+    };
+    Ok(next_msg)
+}
+
+/// Move the counter to `counter`, alerting on thresholds and applying the
+/// bound policy if it leaves its bounds.
+fn step_counter(
+    counters: &mut Counters,
+    feedback: &mut Feedback,
+    now: Instant,
+    counter: i32,
+) -> Option<Message> {
+    let previous = counters.value;
+    if let Some(alert) = threshold_toast(counters, now, counter) {
+        counters.alerts += 1;
+        feedback.toast = Some(alert);
+    }
+    counters.value = counter;
+    let bound = counters.bound;
+    if bound.contains(counter) {
+        return None;
+    }
+
+    match bound.policy {
+        BoundPolicy::Reset => {
+            let text = format!("Counter passed ±{}, reset to 0", bound.limit);
+            feedback.show(Role::Warning, text, now);
+            Some(Message::Reset)
+        }
+        BoundPolicy::Clamp => {
+            counters.value = counter.clamp(-bound.limit, bound.limit);
+            let text = format!("Counter stops at ±{}", bound.limit);
+            feedback.show(Role::Warning, text, now);
+            None
+        }
+        // Ask once when the counter leaves its bounds, not on every step
+        BoundPolicy::Confirm if bound.contains(previous) && counters.confirm.is_none() => {
+            let text = format!("Counter passed ±{}", bound.limit);
+            feedback.show(Role::Warning, text, now);
+            counters.confirm = Some(Confirm {
+                question: format!("The counter passed ±{}. Reset it to 0?", bound.limit),
+                action: Confirmable::ResetCounter,
+            });
+            None
+        }
+        BoundPolicy::Confirm => None,
+    }
+}
+
+/// Alert for a counter change that escalates its threshold level, if any.
+fn threshold_toast(counters: &Counters, now: Instant, counter: i32) -> Option<Toast> {
+    counters
+        .thresholds
+        .alert(counters.value, counter)
+        .map(|level| {
+            Toast::new(
+                level.role(),
+                format!("Counter reached {} ({:?})", counter, level),
+                now,
+            )
+        })
+}
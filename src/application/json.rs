@@ -0,0 +1,77 @@
+use std::time::Instant;
+
+use crate::{json_tree::JsonTree, theme::Role};
+
+use super::{Feedback, Message};
+
+/// Handle the JSON explorer over the output `lines`.
+pub(super) fn reduce(
+    json_tree: &mut Option<JsonTree>,
+    lines: &[String],
+    feedback: &mut Feedback,
+    now: Instant,
+    msg: Message,
+) -> Result<Option<Message>, Message> {
+    let next_msg = match msg {
+        Message::OpenJsonTree => {
+            *json_tree = JsonTree::parse(&lines.join("\n"));
+            if json_tree.is_none() {
+                feedback.show(Role::Info, "Output is not JSON", now);
+            }
+            None
+        }
+        Message::SelectJsonNode(delta) => {
+            if let Some(tree) = json_tree {
+                tree.select(delta);
+            }
+            None
+        }
+        Message::ToggleJsonNode => {
+            if let Some(tree) = json_tree {
+                tree.toggle();
+            }
+            None
+        }
+        Message::CopyJsonPath => json_tree
+            .as_ref()
+            .and_then(JsonTree::selected_path)
+            .map(Message::CopyText),
+        Message::CopyJsonValue => json_tree
+            .as_ref()
+            .and_then(JsonTree::selected_value)
+            .map(Message::CopyText),
+        Message::StartJsonSearch => {
+            if let Some(tree) = json_tree {
+                tree.search = Some(String::new());
+            }
+            None
+        }
+        Message::JsonSearchInput(c) => {
+            if let Some(query) = json_tree.as_mut().and_then(|t| t.search.as_mut()) {
+                query.push(c);
+            }
+            None
+        }
+        Message::JsonSearchBackspace => {
+            if let Some(query) = json_tree.as_mut().and_then(|t| t.search.as_mut()) {
+                query.pop();
+            }
+            None
+        }
+        Message::JsonSearchNext => {
+            let Some(tree) = json_tree else {
+                return Ok(None);
+            };
+            if let Some(query) = tree.search.take() {
+                tree.last_search = query;
+            }
+            if !tree.next_match() && !tree.last_search.is_empty() {
+                let text = format!("No match for `{}`", tree.last_search);
+                feedback.show(Role::Info, text, now);
+            }
+            None
+        }
+        msg => return Err(msg),
+    };
+    Ok(next_msg)
+}
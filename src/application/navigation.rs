@@ -0,0 +1,86 @@
+use crate::{
+    context_menu::{ContextMenu, MenuAction},
+    external::Program,
+    pane::{PaneId, ScreenLayout},
+};
+
+use super::Message;
+
+/// Where the user is on the screen and which of its popups are open.
+#[derive(Debug, Clone)]
+pub(super) struct Screen {
+    pub(super) focused: PaneId,
+    /// Where panes were drawn last, to find what the mouse points at.
+    pub(super) layout: ScreenLayout,
+    pub(super) context_menu: Option<ContextMenu>,
+    /// Release notes shown after an upgrade until dismissed.
+    pub(super) whats_new: Option<String>,
+    /// Show a QR code of the current view's deep link.
+    pub(super) sharing: bool,
+    /// Show the background tasks, for debugging.
+    pub(super) show_tasks: bool,
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Self {
+            focused: PaneId::Live,
+            layout: ScreenLayout::default(),
+            context_menu: None,
+            whats_new: None,
+            sharing: false,
+            show_tasks: false,
+        }
+    }
+}
+
+/// Handle moving around the screen and opening its popups. Focus only moves
+/// to the output pane `with_output`.
+pub(super) fn reduce(
+    screen: &mut Screen,
+    with_output: bool,
+    msg: Message,
+) -> Result<Option<Message>, Message> {
+    let next_msg = match msg {
+        Message::FocusNext => {
+            screen.focused = screen.focused.next(with_output);
+            None
+        }
+        Message::OpenContextMenu { pane, column, row } => {
+            // Menu actions apply to the focused pane
+            if pane != PaneId::Status {
+                screen.focused = pane;
+            }
+            screen.context_menu = ContextMenu::open(pane, column, row, screen.layout.screen.into());
+            None
+        }
+        Message::SelectMenuItem(delta) => {
+            if let Some(menu) = &mut screen.context_menu {
+                menu.select(delta);
+            }
+            None
+        }
+        Message::ChooseMenuItem(index) => {
+            let menu = screen.context_menu.take();
+            let action = menu.as_ref().and_then(|menu| menu.items.get(index));
+            action.map(|action| match action {
+                MenuAction::Copy => Message::Yank,
+                MenuAction::Refresh => Message::RefreshFocused,
+                MenuAction::ViewInPager => Message::OpenExternal(Program::Pager),
+                MenuAction::OpenLink => Message::OpenLink,
+                MenuAction::ToggleWrap => Message::ToggleOutputWrap,
+                MenuAction::ExploreJson => Message::OpenJsonTree,
+            })
+        }
+        Message::ToggleTasks => {
+            screen.show_tasks = !screen.show_tasks;
+            None
+        }
+        Message::ToggleShare => {
+            screen.sharing = !screen.sharing;
+            None
+        }
+        msg => return Err(msg),
+    };
+    Ok(next_msg)
+}
@@ -0,0 +1,40 @@
+use crate::notifications::NotificationCenter;
+
+use super::Message;
+
+/// Handle the notification center.
+pub(super) fn reduce(
+    center: &mut NotificationCenter,
+    msg: Message,
+) -> Result<Option<Message>, Message> {
+    let next_msg = match msg {
+        Message::ToggleNotifications => {
+            center.toggle();
+            None
+        }
+        Message::SelectNotification(delta) => {
+            center.select(delta);
+            center.details = false;
+            None
+        }
+        Message::CycleNotificationFilter => {
+            center.cycle_filter();
+            None
+        }
+        Message::ShowNotificationDetails => {
+            center.details = center.selected().is_some();
+            None
+        }
+        Message::RetryNotification => {
+            let retry = center
+                .selected()
+                .and_then(|notification| notification.retry.clone());
+            if retry.is_some() {
+                center.toggle();
+            }
+            retry.map(Message::RerunQuickCommand)
+        }
+        msg => return Err(msg),
+    };
+    Ok(next_msg)
+}
@@ -0,0 +1,79 @@
+use std::{sync::Arc, time::Instant};
+
+use crate::{clipboard::Copied, prepare::PreparedOutput, scroll_view::ScrollView, theme::Role};
+
+use super::{Feedback, Message};
+
+/// Lines of quick command output kept for the output pane.
+const OUTPUT_MAX_LINES: usize = 1000;
+
+/// The output of the last quick command and how it is viewed.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Output {
+    pub(super) lines: Vec<String>,
+    /// Bumped whenever `lines` change, so drawings of them can be reused.
+    pub(super) version: u64,
+    /// Scroll position and wrapping of the output pane.
+    pub(super) view: ScrollView,
+    /// The output analyzed in the background, possibly for an older version.
+    pub(super) prepared: Option<Arc<PreparedOutput>>,
+    /// The quick command run whose output is shown.
+    pub(super) run: u64,
+}
+
+/// Handle quick command output, the output pane and the results of other
+/// side effects.
+pub(super) fn reduce(
+    output: &mut Output,
+    feedback: &mut Feedback,
+    now: Instant,
+    msg: Message,
+) -> Result<Option<Message>, Message> {
+    match msg {
+        // Left over from a run that was cancelled for a newer one
+        Message::CommandOutput { run, .. } | Message::CommandExited { run, .. }
+            if run != output.run => {}
+        Message::CommandStarted { run, name } => {
+            output.run = run;
+            output.lines.clear();
+            output.version += 1;
+            output.view.scroll = 0;
+            feedback.show(Role::Info, format!("Running `{}`", name), now);
+        }
+        Message::CommandOutput { line, .. } => {
+            output.lines.push(line);
+            let excess = output.lines.len().saturating_sub(OUTPUT_MAX_LINES);
+            output.lines.drain(..excess);
+            output.version += 1;
+        }
+        Message::CommandExited { name, code, .. } => match code {
+            Some(0) => feedback.show(Role::Success, format!("`{}` finished", name), now),
+            Some(code) => feedback.show(
+                Role::Error,
+                format!("`{}` exited with code {}", name, code),
+                now,
+            ),
+            None => feedback.show(
+                Role::Error,
+                format!("`{}` was terminated by a signal", name),
+                now,
+            ),
+        },
+        Message::OutputPrepared(prepared) => output.prepared = Some(prepared),
+        // Clamped to the first row when drawn
+        Message::ScrollOutputUp => output.view.scroll += 5,
+        Message::ScrollOutputDown => output.view.scroll = output.view.scroll.saturating_sub(5),
+        Message::ToggleOutputWrap => output.view.toggle_wrap(),
+        Message::PanOutput(delta) => output.view.pan(delta),
+        Message::ExternalFailed(err) => feedback.error(err, now),
+        Message::Copied(copied) => {
+            let text = match copied {
+                Copied::Local => "Copied to clipboard",
+                Copied::Terminal => "Copied through the terminal",
+            };
+            feedback.show(Role::Success, text, now);
+        }
+        msg => return Err(msg),
+    }
+    Ok(None)
+}
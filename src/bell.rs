@@ -0,0 +1,162 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    process::{Command, Stdio},
+    str::FromStr,
+};
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{tasks, timestamp};
+
+/// Sounding critical alerts, for users who keep the app running while
+/// working elsewhere, e.g.
+///
+/// ```toml
+/// [bell]
+/// enabled = true
+/// sound_command = "paplay /usr/share/sounds/freedesktop/stereo/bell.oga"
+/// quiet_hours = "22:00-07:00"
+/// ```
+///
+/// Only critical threshold alerts ring; warnings stay silent.
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Bell {
+    pub enabled: bool,
+    /// Program to play a sound instead of ringing the terminal bell. Run
+    /// directly, split on whitespace.
+    pub sound_command: Option<String>,
+    /// Local time range in which alerts stay silent, as `HH:MM-HH:MM`. The
+    /// range may span midnight.
+    #[schemars(with = "Option<String>")]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+/// A daily time range, in minutes since local midnight.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct QuietHours {
+    start: u16,
+    end: u16,
+}
+
+impl QuietHours {
+    fn contains(self, minute: u16) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+impl FromStr for QuietHours {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_time = |time: &str| {
+            let (hours, minutes) = time.trim().split_once(':')?;
+            let (hours, minutes) = (hours.parse::<u16>().ok()?, minutes.parse::<u16>().ok()?);
+            (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+        };
+        s.split_once('-')
+            .and_then(|(start, end)| {
+                Some(QuietHours {
+                    start: parse_time(start)?,
+                    end: parse_time(end)?,
+                })
+            })
+            .ok_or_else(|| format!("invalid quiet hours `{}`, expected e.g. `22:00-07:00`", s))
+    }
+}
+
+impl TryFrom<String> for QuietHours {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start / 60,
+            self.start % 60,
+            self.end / 60,
+            self.end % 60
+        )
+    }
+}
+
+impl Bell {
+    /// Ring the bell or play the configured sound, unless disabled or in
+    /// quiet hours.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the sound command cannot be
+    /// started or the bell cannot be written.
+    pub fn ring(&self) -> io::Result<()> {
+        let quiet = self
+            .quiet_hours
+            .is_some_and(|hours| hours.contains(timestamp::local_minute_of_day()));
+        if !self.enabled || quiet {
+            return Ok(());
+        }
+        match &self.sound_command {
+            Some(command_line) => {
+                let mut parts = command_line.split_whitespace();
+                let Some(program) = parts.next() else {
+                    return Ok(());
+                };
+                let mut child = Command::new(program)
+                    .args(parts)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()?;
+                // The sound plays while the app carries on, and is reaped
+                // once it ends so it does not linger as a zombie. It may
+                // still be playing when the app exits, which is fine.
+                tasks::spawn_service("alert sound", move || child.wait());
+                Ok(())
+            }
+            None => {
+                let mut stdout = io::stdout();
+                stdout.write_all(b"\x07")?;
+                stdout.flush()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_hours_may_span_midnight() {
+        let night: QuietHours = "22:00-07:30".parse().unwrap();
+        assert_eq!(night.to_string(), "22:00-07:30");
+        assert!(night.contains(23 * 60));
+        assert!(night.contains(7 * 60 + 29));
+        assert!(!night.contains(7 * 60 + 30));
+        assert!(!night.contains(12 * 60));
+
+        let lunch: QuietHours = "12:00-13:00".parse().unwrap();
+        assert!(lunch.contains(12 * 60 + 30));
+        assert!(!lunch.contains(13 * 60));
+    }
+
+    #[test]
+    fn rejects_invalid_quiet_hours() {
+        assert!("22:00".parse::<QuietHours>().is_err());
+        assert!("25:00-07:00".parse::<QuietHours>().is_err());
+        assert!(toml::from_str::<Bell>("quiet_hours = \"late\"").is_err());
+    }
+}
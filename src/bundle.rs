@@ -0,0 +1,207 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Deserialize;
+
+use crate::persist;
+
+/// A profile bundle as distributed by an admin:
+///
+/// ```toml
+/// public_key = "<hex ed25519 public key>"
+/// signature = "<hex ed25519 signature over payload>"
+/// payload = '''
+/// theme = "deuteranopia"
+/// [profiles.work]
+/// credential_helper = "pass show api/monika"
+/// '''
+/// ```
+///
+/// The payload uses the config file schema and is layered below the user's
+/// own `config.toml`.
+#[derive(Debug, Deserialize)]
+struct BundleFile {
+    public_key: String,
+    signature: String,
+    payload: String,
+}
+
+/// A bundle whose signature checked out against a trusted signer.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    pub name: String,
+    /// Name the user gave the signing key in `trusted_signers`.
+    pub signer: String,
+    pub payload: toml::Table,
+}
+
+/// Directory imported bundles are kept in, next to `config.toml`.
+pub fn dir() -> Option<PathBuf> {
+    persist::config_dir().map(|dir| dir.join("bundles"))
+}
+
+/// Verify `contents` against the `trusted` signers (name to hex public key).
+///
+/// # Errors
+///
+/// This function will return an error if the bundle is malformed, its key is
+/// not trusted, or the signature does not match the payload.
+pub fn verify(
+    name: &str,
+    contents: &str,
+    trusted: &BTreeMap<String, String>,
+) -> color_eyre::Result<Bundle> {
+    let file: BundleFile =
+        toml::from_str(contents).wrap_err_with(|| format!("Bundle `{}` is malformed", name))?;
+
+    let public_key = file.public_key.trim().to_lowercase();
+    let signer = trusted
+        .iter()
+        .find(|(_, key)| key.trim().to_lowercase() == public_key)
+        .map(|(signer, _)| signer.clone())
+        .ok_or_else(|| {
+            eyre!(
+                "Bundle `{}` is signed by an untrusted key. To trust it, add \
+                `<name> = \"{}\"` under [trusted_signers] in your config.",
+                name,
+                public_key
+            )
+        })?;
+
+    let key_bytes: [u8; 32] = hex::decode(&public_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| eyre!("Bundle `{}` has an invalid public key.", name))?;
+    let signature_bytes: [u8; 64] = hex::decode(file.signature.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| eyre!("Bundle `{}` has an invalid signature.", name))?;
+
+    VerifyingKey::from_bytes(&key_bytes)
+        .and_then(|key| {
+            key.verify(
+                file.payload.as_bytes(),
+                &Signature::from_bytes(&signature_bytes),
+            )
+        })
+        .map_err(|_| eyre!("Bundle `{}` failed signature verification.", name))?;
+
+    let mut payload: toml::Table = toml::from_str(&file.payload)
+        .wrap_err_with(|| format!("Bundle `{}` has an invalid payload", name))?;
+    // Trust is the user's decision; a bundle must not extend it
+    payload.remove("trusted_signers");
+
+    Ok(Bundle {
+        name: name.to_string(),
+        signer,
+        payload,
+    })
+}
+
+/// Verify the bundle at `path` and copy it into the bundle directory.
+///
+/// # Errors
+///
+/// This function will return an error if the bundle cannot be read, does not
+/// verify, or cannot be copied.
+pub fn import(path: &Path, trusted: &BTreeMap<String, String>) -> color_eyre::Result<Bundle> {
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| eyre!("Bundle path {} has no file name.", path.display()))?;
+    let contents = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Could not read bundle {}", path.display()))?;
+    let bundle = verify(name, &contents, trusted)?;
+
+    let dir = dir().ok_or_else(|| eyre!("Could not determine the config directory."))?;
+    fs::create_dir_all(&dir)
+        .wrap_err_with(|| format!("Could not create bundle directory {}", dir.display()))?;
+    persist::atomic_write(&dir.join(format!("{}.toml", name)), contents)
+        .wrap_err_with(|| format!("Could not store bundle `{}`", name))?;
+    Ok(bundle)
+}
+
+/// Load and re-verify every imported bundle, in file name order.
+///
+/// # Errors
+///
+/// This function will return an error if any imported bundle no longer
+/// verifies, e.g. because it was edited or its signer is no longer trusted.
+pub fn load_all(trusted: &BTreeMap<String, String>) -> color_eyre::Result<Vec<Bundle>> {
+    let Some(dir) = dir() else {
+        return Ok(Vec::new());
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).wrap_err_with(|| format!("Could not read {}", dir.display())),
+    };
+
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "toml"));
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            let contents = fs::read_to_string(path)
+                .wrap_err_with(|| format!("Could not read bundle {}", path.display()))?;
+            verify(&name, &contents, trusted)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const PAYLOAD: &str = "theme = \"protanopia\"\n[trusted_signers]\nevil = \"00\"\n";
+
+    fn signed_bundle(key: &SigningKey, payload: &str) -> String {
+        format!(
+            "public_key = \"{}\"\nsignature = \"{}\"\npayload = '''\n{}'''\n",
+            hex::encode(key.verifying_key().as_bytes()),
+            hex::encode(key.sign(payload.as_bytes()).to_bytes()),
+            payload
+        )
+    }
+
+    fn trusting(key: &SigningKey) -> BTreeMap<String, String> {
+        BTreeMap::from([(
+            "Acme IT".to_string(),
+            hex::encode(key.verifying_key().as_bytes()),
+        )])
+    }
+
+    #[test]
+    fn verifies_trusted_bundle() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let bundle = verify("acme", &signed_bundle(&key, PAYLOAD), &trusting(&key)).unwrap();
+        assert_eq!(bundle.signer, "Acme IT");
+        assert_eq!(bundle.payload["theme"].as_str(), Some("protanopia"));
+        assert!(!bundle.payload.contains_key("trusted_signers"));
+    }
+
+    #[test]
+    fn rejects_untrusted_signer() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let other = SigningKey::from_bytes(&[8; 32]);
+        assert!(verify("acme", &signed_bundle(&key, PAYLOAD), &trusting(&other)).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_payload() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let tampered = signed_bundle(&key, PAYLOAD).replace("protanopia", "deuteranopia");
+        assert!(verify("acme", &tampered, &trusting(&key)).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{self, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+
+use crate::{links, persist};
+
+/// How long to wait for the terminal to answer the probe.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Queries whose answers reveal what the terminal supports, ending with a
+/// primary device attributes request that every terminal answers, so its
+/// reply marks the end of the probe.
+const PROBE: &str = concat!(
+    // Set an RGB foreground and ask which SGR is in effect (DECRQSS)
+    "\x1b[38;2;1;2;3m\x1bP$qm\x1b\\\x1b[0m",
+    // Ask whether mouse reporting is a known mode (DECRQM)
+    "\x1b[?1000$p",
+    // Ask for the kitty keyboard protocol flags
+    "\x1b[?u",
+    // Primary device attributes
+    "\x1b[c",
+);
+
+/// What the terminal can do, as far as it can be found out.
+///
+/// OSC 52 and OSC 8 cannot be queried, so they are inferred from what the
+/// terminal announces in its environment.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub truecolor: bool,
+    pub mouse: bool,
+    pub kitty_keyboard: bool,
+    pub osc52: bool,
+    pub osc8: bool,
+    pub sixel: bool,
+}
+
+impl Capabilities {
+    /// The cached capabilities of this terminal, probing it on a cache miss.
+    ///
+    /// The terminal must be in raw mode, and nothing else may be reading
+    /// input yet, since the answers arrive on stdin.
+    pub fn load_or_probe() -> Self {
+        let key = terminal_key();
+        let mut cache = read_cache();
+        if let Some(capabilities) = cache.get(&key) {
+            return *capabilities;
+        }
+        let capabilities = Self::probe();
+        cache.insert(key, capabilities);
+        // A cache that cannot be written only means probing again next time
+        let _ = write_cache(&cache);
+        capabilities
+    }
+
+    /// Probe the terminal again and replace its cache entry.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the cache cannot be written.
+    pub fn reprobe() -> color_eyre::Result<Self> {
+        let capabilities = Self::probe();
+        let mut cache = read_cache();
+        cache.insert(terminal_key(), capabilities);
+        write_cache(&cache)?;
+        Ok(capabilities)
+    }
+
+    fn probe() -> Self {
+        let replies = query(PROBE).unwrap_or_default();
+        Self {
+            osc52: osc52_assumed(),
+            osc8: links::supported(),
+            ..parse_replies(&replies)
+        }
+    }
+
+    /// Each capability with whether it is available, for display.
+    pub fn list(&self) -> [(&'static str, bool); 6] {
+        [
+            ("truecolor", self.truecolor),
+            ("mouse", self.mouse),
+            ("kitty keyboard", self.kitty_keyboard),
+            ("OSC 52 clipboard", self.osc52),
+            ("OSC 8 hyperlinks", self.osc8),
+            ("sixel", self.sixel),
+        ]
+    }
+}
+
+/// Identifies a terminal across sessions: the terminfo name plus the
+/// emulator and its version, since upgrades may add features.
+pub fn terminal_key() -> String {
+    let var = |name| env::var(name).unwrap_or_default();
+    let program = format!("{} {}", var("TERM_PROGRAM"), var("TERM_PROGRAM_VERSION"));
+    match program.trim() {
+        "" => var("TERM"),
+        program => format!("{}|{}", var("TERM"), program),
+    }
+}
+
+/// Location of the capability cache, e.g. `~/.cache/monika/terminals.toml`.
+fn cache_path() -> Option<PathBuf> {
+    persist::cache_dir().map(|dir| dir.join("terminals.toml"))
+}
+
+fn read_cache() -> BTreeMap<String, Capabilities> {
+    cache_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| toml::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn write_cache(cache: &BTreeMap<String, Capabilities>) -> color_eyre::Result<()> {
+    let Some(path) = cache_path() else {
+        return Ok(());
+    };
+    let contents = toml::to_string(cache).wrap_err("Could not serialize terminal cache")?;
+    persist::write(&path, contents).wrap_err_with(|| format!("Could not write {}", path.display()))
+}
+
+/// Terminals known to accept OSC 52 writes, including tmux which forwards
+/// them to the outer terminal.
+fn osc52_assumed() -> bool {
+    let var = |name| env::var(name).unwrap_or_default();
+    matches!(
+        var("TERM_PROGRAM").as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "tmux"
+    ) || env::var_os("KITTY_WINDOW_ID").is_some()
+        || env::var_os("WT_SESSION").is_some()
+        || var("TERM").starts_with("foot")
+        || var("TERM").starts_with("alacritty")
+}
+
+/// Interpret the terminal's answers to [`PROBE`].
+fn parse_replies(replies: &str) -> Capabilities {
+    // DECRQSS answers `1$r` with the SGR in effect, in `;` or `:` form
+    let truecolor = replies
+        .split("\x1bP1$r")
+        .skip(1)
+        .any(|reply| reply.replace(':', ";").contains("2;1;2;3"));
+    // DECRPM answers 0 for unknown modes, 1-4 for known ones
+    let mouse = replies
+        .split("\x1b[?1000;")
+        .skip(1)
+        .any(|reply| matches!(reply.as_bytes(), [b'1'..=b'4', b'$', b'y', ..]));
+    let csi_replies: Vec<&str> = replies.split("\x1b[?").skip(1).collect();
+    let kitty_keyboard = csi_replies.iter().any(|reply| {
+        reply
+            .split_once('u')
+            .is_some_and(|(flags, _)| flags.bytes().all(|b| b.is_ascii_digit()))
+    });
+    let sixel = csi_replies.iter().any(|reply| {
+        reply
+            .split_once('c')
+            .is_some_and(|(attributes, _)| attributes.split(';').skip(1).any(|a| a == "4"))
+    });
+    Capabilities {
+        truecolor,
+        mouse,
+        kitty_keyboard,
+        sixel,
+        ..Capabilities::default()
+    }
+}
+
+/// Write `queries` to the terminal and collect its answers until the primary
+/// device attributes reply arrives or [`PROBE_TIMEOUT`] passes.
+#[cfg(unix)]
+fn query(queries: &str) -> io::Result<String> {
+    use std::{io::Read, os::fd::AsRawFd};
+
+    let mut stdout = io::stdout();
+    stdout.write_all(queries.as_bytes())?;
+    stdout.flush()?;
+
+    let stdin = io::stdin();
+    let mut stdin = stdin.lock();
+    let deadline = Instant::now() + PROBE_TIMEOUT;
+    let mut replies = Vec::new();
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        let mut fd = libc::pollfd {
+            fd: stdin.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` is a valid pollfd for the duration of the call
+        let ready = unsafe { libc::poll(&mut fd, 1, left.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            break;
+        }
+        let mut chunk = [0; 256];
+        let read = stdin.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        replies.extend_from_slice(&chunk[..read]);
+        if ends_with_device_attributes(&replies) {
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&replies).into_owned())
+}
+
+/// Windows consoles do not answer queries on stdin; report nothing.
+#[cfg(not(unix))]
+fn query(_: &str) -> io::Result<String> {
+    Ok(String::new())
+}
+
+fn ends_with_device_attributes(replies: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(replies);
+    text.rsplit_once("\x1b[?").is_some_and(|(_, last)| {
+        last.strip_suffix('c')
+            .is_some_and(|attributes| attributes.bytes().all(|b| b.is_ascii_digit() || b == b';'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_capable_terminal() {
+        let replies = "\x1bP1$r0;38:2:1:2:3m\x1b\\\x1b[?1000;2$y\x1b[?0u\x1b[?62;4;22c";
+        assert!(ends_with_device_attributes(replies.as_bytes()));
+        assert_eq!(
+            parse_replies(replies),
+            Capabilities {
+                truecolor: true,
+                mouse: true,
+                kitty_keyboard: true,
+                sixel: true,
+                ..Capabilities::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_basic_terminal() {
+        // Only device attributes are answered, without sixel
+        let replies = "\x1b[?1;2c";
+        assert!(ends_with_device_attributes(replies.as_bytes()));
+        assert_eq!(parse_replies(replies), Capabilities::default());
+        assert!(!ends_with_device_attributes(b"\x1b[?0u"));
+    }
+}
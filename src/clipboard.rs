@@ -0,0 +1,82 @@
+use std::io::{self, Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::eyre::{eyre, WrapErr};
+
+/// Largest text sent over OSC 52. Its base64 encoding fits in 100 000 bytes,
+/// which is what most terminals accept before dropping the sequence.
+pub const OSC52_MAX_BYTES: usize = 75_000;
+
+/// Where copied text ended up.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Copied {
+    /// The system clipboard of the machine this runs on.
+    Local,
+    /// The clipboard of the user's terminal, via an OSC 52 escape sequence.
+    Terminal,
+}
+
+/// Copy `text` to the system clipboard, falling back to OSC 52 when no
+/// clipboard is reachable (e.g. over SSH) and `osc52` is enabled.
+///
+/// # Errors
+///
+/// This function will return an error if there is no local clipboard and
+/// OSC 52 is disabled, the text is too long for it, or stdout is unwritable.
+pub fn copy(text: &str, osc52: bool) -> color_eyre::Result<Copied> {
+    let Err(err) = copy_local(text) else {
+        return Ok(Copied::Local);
+    };
+    if !osc52 {
+        return Err(eyre!(
+            "No clipboard available ({}). Set osc52_clipboard = true to copy through \
+             a terminal that supports OSC 52.",
+            err
+        ));
+    }
+    if text.len() > OSC52_MAX_BYTES {
+        return Err(eyre!(
+            "Too long to copy through the terminal ({} bytes, at most {}).",
+            text.len(),
+            OSC52_MAX_BYTES
+        ));
+    }
+
+    let mut stdout = io::stdout();
+    stdout
+        .write_all(osc52_sequence(text).as_bytes())
+        .and_then(|()| stdout.flush())
+        .wrap_err("Could not write to the terminal")?;
+    Ok(Copied::Terminal)
+}
+
+#[cfg(feature = "system-clipboard")]
+fn copy_local(text: &str) -> Result<(), arboard::Error> {
+    arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text))
+}
+
+#[cfg(not(feature = "system-clipboard"))]
+fn copy_local(_: &str) -> Result<(), &'static str> {
+    Err("built without system clipboard support")
+}
+
+/// The escape sequence asking the terminal to put `text` on its clipboard.
+fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_osc52() {
+        assert_eq!(osc52_sequence("hello"), "\x1b]52;c;aGVsbG8=\x07");
+    }
+
+    #[test]
+    fn limit_fits_terminals() {
+        assert!(STANDARD.encode("x".repeat(OSC52_MAX_BYTES)).len() <= 100_000);
+        assert!(STANDARD.encode("x".repeat(OSC52_MAX_BYTES + 1)).len() > 100_000);
+    }
+}
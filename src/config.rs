@@ -0,0 +1,297 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::WrapErr;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{
+    bell::Bell,
+    bundle::{self, Bundle},
+    persist,
+    policy::Policy,
+    quick_command::QuickCommand,
+    remote_config::{self, RemoteConfig},
+    rules::{Bound, Thresholds},
+    theme::ThemePreset,
+};
+
+/// User settings read from `config.toml` in the platform config directory,
+/// layered over any imported signed bundles and under the system policy.
+///
+/// Every field has a default, so a missing file or a partial file is valid.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Config {
+    pub theme: ThemePreset,
+    /// Thresholds that color the counter and optionally raise alerts.
+    pub counter: Thresholds,
+    /// Ringing on critical alerts.
+    pub bell: Bell,
+    /// What happens when the counter goes too far.
+    pub counter_bound: Bound,
+    /// Commands that may be launched from the TUI with the number keys.
+    pub quick_commands: Vec<QuickCommand>,
+    /// Per-profile settings, keyed by profile name (`[profiles.work]`).
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    /// Keys allowed to sign profile bundles, as name to hex public key.
+    pub trusted_signers: BTreeMap<String, String>,
+    /// HTTPS address of a signed bundle with organization defaults, fetched
+    /// at startup and layered below everything else.
+    pub config_url: Option<String>,
+    /// Leave the terminal title alone instead of showing the app state.
+    pub disable_terminal_title: bool,
+    /// Leave the mouse to the terminal, e.g. for selecting text, instead of
+    /// opening context menus on right-click.
+    pub disable_mouse: bool,
+    /// Show JSON output in plain text rather than colored by syntax.
+    pub disable_syntax_highlighting: bool,
+    /// Copy through the terminal (OSC 52) when no local clipboard is
+    /// reachable, e.g. over SSH.
+    pub osc52_clipboard: bool,
+    /// Draw time in milliseconds above which a frame is reported as slow on
+    /// the debug screen (`D`). Defaults to 16, about 60 frames a second.
+    pub frame_budget_ms: Option<u64>,
+    /// Opt-in flags for unfinished features.
+    pub experimental: Experimental,
+    /// Defaults fetched from `config_url`, if set.
+    #[serde(skip)]
+    pub remote: Option<RemoteConfig>,
+    /// Bundles that contributed to this config, lowest precedence first.
+    #[serde(skip)]
+    pub bundles: Vec<Bundle>,
+    /// Organization policy whose locked settings override all layers.
+    #[serde(skip)]
+    pub policy: Option<Policy>,
+    /// Started with `--safe-mode`: user settings were left out.
+    #[serde(skip)]
+    pub safe_mode: bool,
+    /// The merged layers as read, before interpretation.
+    #[serde(skip)]
+    pub table: toml::Table,
+}
+
+/// Settings that differ between profiles.
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ProfileConfig {
+    /// Program printing the API key on stdout, e.g. `pass show api/monika`.
+    pub credential_helper: Option<String>,
+    /// `~/.netrc` machine whose password is used as the API key.
+    pub netrc_machine: Option<String>,
+}
+
+/// Unfinished features, off unless enabled, e.g.
+///
+/// ```toml
+/// [experimental]
+/// web_mirror = true
+/// ```
+#[derive(Debug, Default, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Experimental {
+    /// `run --mirror`, serving the TUI to a browser.
+    pub web_mirror: bool,
+}
+
+impl Config {
+    /// Location of the config file, e.g. `~/.config/monika/config.toml`.
+    pub fn path() -> Option<PathBuf> {
+        persist::config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Settings of `profile`, or defaults if it has no section.
+    pub fn profile(&self, profile: &str) -> ProfileConfig {
+        self.profiles.get(profile).cloned().unwrap_or_default()
+    }
+
+    /// Load the config file over the imported bundles and the remote
+    /// defaults, falling back to built-in defaults where none sets a value.
+    /// Settings locked by the system policy replace whatever the other
+    /// layers say. Remote defaults that cannot be fetched or do not verify
+    /// are left out with a warning.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config or policy file exists
+    /// but cannot be read, if an imported bundle does not verify, or if the
+    /// merged result is not valid for this schema.
+    pub fn load() -> color_eyre::Result<Self> {
+        Self::load_layers(true)
+    }
+
+    /// Like [`Config::load`], but without fetching the remote defaults, for
+    /// commands that do not depend on them (e.g. managing credentials).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config or policy file exists
+    /// but cannot be read, if an imported bundle does not verify, or if the
+    /// merged result is not valid for this schema.
+    pub fn load_local() -> color_eyre::Result<Self> {
+        Self::load_layers(false)
+    }
+
+    fn load_layers(with_remote: bool) -> color_eyre::Result<Self> {
+        let mut local = match Self::path() {
+            Some(path) => read_table(&path)?,
+            None => toml::Table::new(),
+        };
+        let policy = Policy::load()?;
+        if let Some(policy) = &policy {
+            merge(&mut local, policy.locked.clone());
+        }
+
+        // Only the user's own file (or the policy) decides which signers are
+        // trusted
+        let trusted: BTreeMap<String, String> = local
+            .get("trusted_signers")
+            .cloned()
+            .map(|signers| signers.try_into())
+            .transpose()
+            .wrap_err("Invalid [trusted_signers] in config file")?
+            .unwrap_or_default();
+        let bundles = bundle::load_all(&trusted)?;
+        let remote = local
+            .get("config_url")
+            .and_then(toml::Value::as_str)
+            .filter(|_| with_remote)
+            .and_then(|url| match remote_config::fetch(url, &trusted) {
+                Ok(remote) => Some(remote),
+                Err(err) => {
+                    // The other layers still make a usable config
+                    eprintln!(
+                        "Warning: continuing without the defaults from {}: {}",
+                        url, err
+                    );
+                    None
+                }
+            });
+
+        let mut merged = toml::Table::new();
+        if let Some(remote) = &remote {
+            merge(&mut merged, remote.bundle.payload.clone());
+        }
+        for bundle in &bundles {
+            merge(&mut merged, bundle.payload.clone());
+        }
+        merge(&mut merged, local);
+
+        let mut config = Self::from_table(merged)?;
+        config.remote = remote;
+        config.bundles = bundles;
+        config.policy = policy;
+        Ok(config)
+    }
+
+    /// Defaults under the system policy only, for `--safe-mode`: bundles and
+    /// the config file are skipped, and nothing that runs programs is kept
+    /// (quick commands, credential helpers, the alert sound).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the policy file exists but
+    /// cannot be read, or locks settings that are not valid for this schema.
+    pub fn load_safe() -> color_eyre::Result<Self> {
+        let policy = Policy::load()?;
+        let locked = policy
+            .as_ref()
+            .map(|policy| policy.locked.clone())
+            .unwrap_or_default();
+        let mut config = Self::from_table(locked)?;
+        config.strip_programs();
+        config.policy = policy;
+        config.safe_mode = true;
+        Ok(config)
+    }
+
+    /// Settings from a snapshot, which may come from anyone: nothing that
+    /// runs programs is kept (quick commands, credential helpers, the alert
+    /// sound), and the system policy's locked settings still win.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `table` is not valid for this
+    /// schema, or if the policy cannot be read.
+    pub fn from_snapshot(table: toml::Table) -> color_eyre::Result<Self> {
+        Self::from_snapshot_under(table, Policy::load()?)
+    }
+
+    fn from_snapshot_under(
+        mut table: toml::Table,
+        policy: Option<Policy>,
+    ) -> color_eyre::Result<Self> {
+        if let Some(policy) = &policy {
+            merge(&mut table, policy.locked.clone());
+        }
+        let mut config = Self::from_table(table)?;
+        config.strip_programs();
+        config.policy = policy;
+        Ok(config)
+    }
+
+    fn strip_programs(&mut self) {
+        self.quick_commands.clear();
+        self.profiles.clear();
+        self.bell.sound_command = None;
+    }
+
+    /// Interpret already merged settings.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `table` is not valid for this
+    /// schema.
+    pub fn from_table(table: toml::Table) -> color_eyre::Result<Self> {
+        let mut config: Self = table.clone().try_into().wrap_err("Invalid configuration")?;
+        config.table = table;
+        Ok(config)
+    }
+}
+
+fn read_table(path: &Path) -> color_eyre::Result<toml::Table> {
+    match fs::read_to_string(path) {
+        Ok(contents) => toml::from_str(&contents)
+            .wrap_err_with(|| format!("Invalid config file {}", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(toml::Table::new()),
+        Err(err) => {
+            Err(err).wrap_err_with(|| format!("Could not read config file {}", path.display()))
+        }
+    }
+}
+
+/// Merge `layer` into `base`; values in `layer` win, tables merge key by key.
+fn merge(base: &mut toml::Table, layer: toml::Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(layer)) => merge(base, layer),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn locked_settings_win_over_a_snapshot() {
+        let snapshot = toml::from_str("[experimental]\nweb_mirror = true").unwrap();
+        let policy = Policy {
+            locked: toml::from_str("[experimental]\nweb_mirror = false").unwrap(),
+            source: PathBuf::from("/etc/monika/policy.toml"),
+        };
+
+        let config = Config::from_snapshot_under(snapshot, Some(policy)).unwrap();
+
+        assert!(!config.experimental.web_mirror);
+        assert!(config.policy.is_some());
+    }
+}
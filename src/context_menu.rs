@@ -0,0 +1,99 @@
+use ratatui::layout::Rect;
+
+use crate::pane::PaneId;
+
+/// Something a context menu offers to do.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum MenuAction {
+    Copy,
+    Refresh,
+    ViewInPager,
+    OpenLink,
+    ToggleWrap,
+    ExploreJson,
+}
+
+impl MenuAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Copy => "Copy",
+            Self::Refresh => "Refresh",
+            Self::ViewInPager => "View in pager",
+            Self::OpenLink => "Open link in browser",
+            Self::ToggleWrap => "Toggle wrap",
+            Self::ExploreJson => "Explore as JSON",
+        }
+    }
+
+    /// What makes sense for `pane`.
+    pub fn for_pane(pane: PaneId) -> &'static [Self] {
+        match pane {
+            PaneId::Live | PaneId::Manual => &[Self::Copy, Self::Refresh, Self::ViewInPager],
+            PaneId::Output => &[Self::OpenLink, Self::ToggleWrap, Self::ExploreJson],
+            PaneId::Status => &[],
+        }
+    }
+}
+
+/// A menu of actions for a pane, opened at a screen position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextMenu {
+    pub pane: PaneId,
+    pub items: &'static [MenuAction],
+    pub selected: usize,
+    /// Where the menu is drawn, within the screen it was opened on.
+    pub area: Rect,
+}
+
+impl ContextMenu {
+    /// A menu for `pane` with its corner at `column`, `row`, moved up or left
+    /// as needed to fit on `screen`. `None` if the pane has no actions.
+    pub fn open(pane: PaneId, column: u16, row: u16, screen: Rect) -> Option<Self> {
+        let items = MenuAction::for_pane(pane);
+        let longest = items.iter().map(|item| item.label().len()).max()?;
+        // Room for the border and a space on either side of the labels
+        let width = (longest as u16 + 4).min(screen.width);
+        let height = (items.len() as u16 + 2).min(screen.height);
+        let x = column
+            .min(screen.right().saturating_sub(width))
+            .max(screen.x);
+        let y = row
+            .min(screen.bottom().saturating_sub(height))
+            .max(screen.y);
+        Some(Self {
+            pane,
+            items,
+            selected: 0,
+            area: Rect::new(x, y, width, height),
+        })
+    }
+
+    pub fn select(&mut self, delta: isize) {
+        let last = self.items.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// The item drawn at `column`, `row`, if any.
+    pub fn item_at(&self, column: u16, row: u16) -> Option<usize> {
+        let inside = column > self.area.x && column + 1 < self.area.right();
+        let index = usize::from(row.checked_sub(self.area.y + 1)?);
+        (inside && index < self.items.len()).then_some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_on_screen_and_finds_items() {
+        let screen = Rect::new(0, 0, 80, 24);
+        let menu = ContextMenu::open(PaneId::Output, 78, 23, screen).unwrap();
+        assert_eq!(menu.area, Rect::new(56, 19, 24, 5));
+        assert_eq!(menu.item_at(60, 20), Some(0));
+        assert_eq!(menu.item_at(60, 22), Some(2));
+        assert_eq!(menu.item_at(60, 23), None);
+        assert_eq!(menu.item_at(56, 20), None);
+        assert!(ContextMenu::open(PaneId::Status, 0, 0, screen).is_none());
+    }
+}
@@ -0,0 +1,516 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt, fs, io,
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::eyre::{eyre, Report, WrapErr};
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+
+use crate::{application::API_KEY_LEN, config::ProfileConfig, persist};
+
+/// Keychain service all credentials are stored under.
+pub const KEYRING_SERVICE: &str = "monika-cli";
+
+/// Environment variable child processes receive the API key in.
+pub const API_KEY_ENV: &str = "MONIKA_API_KEY";
+
+/// Characters that do not show when pasted, but end up in the key. Password
+/// managers and web pages sometimes add them around or inside secrets.
+const INVISIBLE: [char; 6] = [
+    '\u{200b}', // zero width space
+    '\u{200c}', // zero width non-joiner
+    '\u{200d}', // zero width joiner
+    '\u{2060}', // word joiner
+    '\u{feff}', // byte order mark
+    '\u{00ad}', // soft hyphen
+];
+
+/// Attempts at a keychain operation before a platform failure is reported.
+const KEYRING_ATTEMPTS: u32 = 4;
+/// Wait before the first retry, doubled for each one after.
+const KEYRING_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The keychain entry holding the API key of `profile`.
+///
+/// The default profile keeps the original `api_key` account name so keys
+/// stored before profiles existed are still found.
+///
+/// # Errors
+///
+/// This function will return an error if the platform keychain is unavailable.
+pub fn keyring_entry(profile: &str) -> keyring::Result<Entry> {
+    if profile == "default" {
+        Entry::new(KEYRING_SERVICE, "api_key")
+    } else {
+        Entry::new(KEYRING_SERVICE, &format!("api_key.{}", profile))
+    }
+}
+
+/// Run the keychain operation `op`, retrying with backoff on platform
+/// failures. Secret Service in particular returns transient D-Bus errors for
+/// a moment after login.
+///
+/// # Errors
+///
+/// This function will return the error of the last attempt, or the first
+/// error that retrying cannot fix (e.g. a missing entry or a locked keychain).
+pub fn with_retries<T>(mut op: impl FnMut() -> keyring::Result<T>) -> keyring::Result<T> {
+    let mut delay = KEYRING_BACKOFF;
+    for _ in 1..KEYRING_ATTEMPTS {
+        match op() {
+            Err(keyring::Error::PlatformFailure(_)) => {
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    op()
+}
+
+/// Explain a keychain failure in terms of what the user can do about it.
+pub fn keyring_error(err: keyring::Error) -> Report {
+    eyre!(describe_keyring_error(&err))
+}
+
+fn describe_keyring_error(err: &keyring::Error) -> String {
+    match err {
+        keyring::Error::NoStorageAccess(err) => format!(
+            "The keychain is locked or access was denied ({}). Unlock it and try again.",
+            err
+        ),
+        err => format!("The keychain is unavailable: {}", err),
+    }
+}
+
+/// Why `login` could not store an API key.
+#[derive(Debug)]
+pub enum LoginError {
+    /// The key cannot be an API key; nothing was stored.
+    InvalidFormat(String),
+    /// The keychain refused the key.
+    StoreFailed(keyring::Error),
+    /// The key was stored, but reading it back failed or gave something else.
+    VerifyFailed(Option<keyring::Error>),
+}
+
+impl fmt::Display for LoginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoginError::InvalidFormat(reason) => {
+                write!(f, "Not a valid API key, nothing was stored: {}", reason)
+            }
+            LoginError::StoreFailed(err) => {
+                write!(
+                    f,
+                    "Could not store the API key. {}",
+                    describe_keyring_error(err)
+                )
+            }
+            LoginError::VerifyFailed(Some(err)) => write!(
+                f,
+                "The API key was stored, but could not be read back. {}",
+                describe_keyring_error(err)
+            ),
+            LoginError::VerifyFailed(None) => write!(
+                f,
+                "The API key was stored, but the keychain returned a different key. \
+                Run `monika login` again."
+            ),
+        }
+    }
+}
+
+impl Error for LoginError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoginError::StoreFailed(err) | LoginError::VerifyFailed(Some(err)) => Some(err),
+            LoginError::InvalidFormat(_) | LoginError::VerifyFailed(None) => None,
+        }
+    }
+}
+
+/// Check that `api_key` looks like an API key, before anything is stored.
+///
+/// # Errors
+///
+/// This function will return an error describing what is wrong with the key.
+pub fn validate_key(api_key: &str) -> Result<(), LoginError> {
+    if let Some(c) = api_key.chars().find(|c| !c.is_ascii_graphic()) {
+        return Err(LoginError::InvalidFormat(format!(
+            "it contains `{}` (U+{:04X}), but API keys are plain ASCII.",
+            c,
+            u32::from(c)
+        )));
+    }
+    if api_key.len() != API_KEY_LEN {
+        return Err(LoginError::InvalidFormat(format!(
+            "expected {} characters, found {}.",
+            API_KEY_LEN,
+            api_key.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Validate `api_key`, store it as the key of `profile`, and read it back to
+/// make sure the keychain kept it.
+///
+/// # Errors
+///
+/// This function will return an error saying which step failed.
+pub fn store_key(profile: &str, api_key: &str) -> Result<(), LoginError> {
+    validate_key(api_key)?;
+    let entry = keyring_entry(profile).map_err(LoginError::StoreFailed)?;
+    with_retries(|| entry.set_password(api_key)).map_err(LoginError::StoreFailed)?;
+    match with_retries(|| entry.get_password()) {
+        Ok(stored) if stored == api_key => {
+            update_ledger(|ledger| {
+                ledger.insert(
+                    profile.to_string(),
+                    KeyRecord {
+                        created: Some(now()),
+                        last_used: None,
+                    },
+                );
+            });
+            Ok(())
+        }
+        Ok(_) => Err(LoginError::VerifyFailed(None)),
+        Err(err) => Err(LoginError::VerifyFailed(Some(err))),
+    }
+}
+
+/// Find the API key of `profile`.
+///
+/// A configured credential helper is authoritative. Otherwise the keychain
+/// is tried first, then `~/.netrc` if the profile names a machine for it.
+///
+/// # Errors
+///
+/// This function will return an error if the credential helper fails, or if
+/// no source has a key for the profile. A keychain that could not be read,
+/// e.g. because it is locked, is reported as such rather than as a missing
+/// key.
+pub fn resolve(profile: &str, settings: &ProfileConfig) -> color_eyre::Result<String> {
+    if let Some(helper) = &settings.credential_helper {
+        return run_credential_helper(helper);
+    }
+
+    let keychain_error = match with_retries(|| keyring_entry(profile)?.get_password()) {
+        Ok(api_key) => {
+            update_ledger(|ledger| {
+                ledger.entry(profile.to_string()).or_default().last_used = Some(now());
+            });
+            return Ok(api_key);
+        }
+        Err(keyring::Error::NoEntry) => None,
+        Err(err) => Some(err),
+    };
+
+    if let Some(machine) = &settings.netrc_machine {
+        if let Some(password) = read_netrc_password(machine)? {
+            return Ok(password);
+        }
+    }
+
+    if let Some(err) = keychain_error {
+        return Err(keyring_error(err));
+    }
+    Err(eyre!(
+        "No API key found for profile `{}`. Please run `monika login` to store an API key.",
+        profile
+    ))
+}
+
+/// Remove the keychain key of `profile`, returning whether there was one.
+///
+/// # Errors
+///
+/// This function will return an error if the keychain cannot be accessed.
+pub fn delete_key(profile: &str) -> keyring::Result<bool> {
+    let deleted = match with_retries(|| keyring_entry(profile)?.delete_credential()) {
+        Ok(()) => true,
+        Err(keyring::Error::NoEntry) => false,
+        Err(err) => return Err(err),
+    };
+    update_ledger(|ledger| {
+        ledger.remove(profile);
+    });
+    Ok(deleted)
+}
+
+/// The keychain key of `profile`, if one is stored, without marking it used.
+///
+/// # Errors
+///
+/// This function will return an error if the keychain cannot be accessed.
+pub fn stored_key(profile: &str) -> keyring::Result<Option<String>> {
+    match with_retries(|| keyring_entry(profile)?.get_password()) {
+        Ok(api_key) => Ok(Some(api_key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Show only the start of `api_key`, e.g. `sk_t…`.
+pub fn mask(api_key: &str) -> String {
+    let start: String = api_key.chars().take(4).collect();
+    format!("{}…", start)
+}
+
+/// What the tool knows of a keychain key, in seconds since the epoch.
+/// Keychains record neither, so `login` and key lookups note them down.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub struct KeyRecord {
+    /// When `login` stored it; unknown for keys stored by older versions.
+    pub created: Option<u64>,
+    pub last_used: Option<u64>,
+}
+
+impl KeyRecord {
+    /// Whether the key has not been used, or stored if never used, for
+    /// `unused_for` at `now`. Keys of unknown age are never stale.
+    pub fn is_stale(&self, now: u64, unused_for: Duration) -> bool {
+        self.last_used
+            .or(self.created)
+            .is_some_and(|at| now.saturating_sub(at) >= unused_for.as_secs())
+    }
+}
+
+/// Where key records are kept, e.g. `~/.local/state/monika/credentials.toml`.
+fn ledger_path() -> Option<PathBuf> {
+    persist::state_dir().map(|dir| dir.join("credentials.toml"))
+}
+
+/// The recorded keys by profile.
+pub fn ledger() -> BTreeMap<String, KeyRecord> {
+    ledger_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| toml::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Change the key records. Failing to write them only loses timestamps, so
+/// it never fails the operation that is being recorded.
+fn update_ledger(change: impl FnOnce(&mut BTreeMap<String, KeyRecord>)) {
+    let Some(path) = ledger_path() else {
+        return;
+    };
+    let mut ledger = ledger();
+    change(&mut ledger);
+    if let Ok(contents) = toml::to_string(&ledger) {
+        let _ = persist::write(&path, contents);
+    }
+}
+
+/// Seconds since the epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Run `helper` and take the first line of its stdout as the API key.
+///
+/// The helper is executed directly rather than through a shell, and its
+/// output is never included in error messages.
+fn run_credential_helper(helper: &str) -> color_eyre::Result<String> {
+    let mut parts = helper.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| eyre!("The configured credential helper is empty."))?;
+
+    let output = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .wrap_err_with(|| format!("Could not run credential helper `{}`", program))?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "Credential helper `{}` exited with {}.",
+            program,
+            output.status
+        ));
+    }
+
+    String::from_utf8(output.stdout)
+        .ok()
+        .and_then(|stdout| stdout.lines().next().map(|line| line.trim().to_string()))
+        .filter(|api_key| !api_key.is_empty())
+        .ok_or_else(|| eyre!("Credential helper `{}` did not print a key.", program))
+}
+
+fn read_netrc_password(machine: &str) -> color_eyre::Result<Option<String>> {
+    let Some(path) = dirs::home_dir().map(|home| home.join(".netrc")) else {
+        return Ok(None);
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(netrc_password(&contents, machine)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).wrap_err_with(|| format!("Could not read {}", path.display())),
+    }
+}
+
+/// Look up the password for `machine` in netrc `contents`, falling back to
+/// the `default` entry.
+fn netrc_password(contents: &str, machine: &str) -> Option<String> {
+    let mut tokens = contents.split_whitespace();
+    let mut current: Option<&str> = None;
+    let mut matched = None;
+    let mut fallback = None;
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => current = tokens.next(),
+            "default" => current = Some(""),
+            "password" => {
+                let password = tokens.next().map(str::to_string);
+                match current {
+                    Some(name) if name == machine => matched = matched.or(password),
+                    Some("") => fallback = fallback.or(password),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    matched.or(fallback)
+}
+
+/// An API key as typed or pasted, cleaned up for validation.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PastedKey {
+    pub key: String,
+    /// Invisible characters that were removed, worth a warning since they
+    /// hint at a copy from the wrong place.
+    pub invisible: Vec<char>,
+}
+
+/// Clean up a pasted API key: surrounding whitespace, line breaks and spaces
+/// from wrapping, and invisible characters are removed. API keys never
+/// contain any of these.
+pub fn normalize_key(raw: &str) -> PastedKey {
+    let invisible = raw.chars().filter(|c| INVISIBLE.contains(c)).collect();
+    let key = raw
+        .chars()
+        .filter(|c| !c.is_whitespace() && !INVISIBLE.contains(c))
+        .collect();
+    PastedKey { key, invisible }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn retries_only_platform_failures() {
+        let attempts = Cell::new(0);
+        let result: keyring::Result<()> = with_retries(|| {
+            attempts.set(attempts.get() + 1);
+            Err(keyring::Error::PlatformFailure("D-Bus not ready".into()))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), KEYRING_ATTEMPTS);
+
+        attempts.set(0);
+        let result: keyring::Result<()> = with_retries(|| {
+            attempts.set(attempts.get() + 1);
+            Err(keyring::Error::NoEntry)
+        });
+        assert!(matches!(result, Err(keyring::Error::NoEntry)));
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    fn rejects_malformed_keys_before_storing() {
+        let valid = "a".repeat(API_KEY_LEN);
+        assert!(validate_key(&valid).is_ok());
+        assert_eq!(
+            validate_key("short").unwrap_err().to_string(),
+            format!(
+                "Not a valid API key, nothing was stored: expected {} characters, found 5.",
+                API_KEY_LEN
+            )
+        );
+        let accented = format!("é{}", "a".repeat(API_KEY_LEN - 2));
+        assert!(matches!(
+            validate_key(&accented),
+            Err(LoginError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn normalizes_pasted_keys() {
+        assert_eq!(
+            normalize_key("  abcd\r\nefgh \n"),
+            PastedKey {
+                key: "abcdefgh".to_string(),
+                invisible: Vec::new(),
+            }
+        );
+        assert_eq!(
+            normalize_key("\u{feff}ab\u{200b}cd"),
+            PastedKey {
+                key: "abcd".to_string(),
+                invisible: vec!['\u{feff}', '\u{200b}'],
+            }
+        );
+    }
+
+    #[test]
+    fn stale_keys_by_last_use() {
+        const DAY: u64 = 24 * 60 * 60;
+        let unused_for = Duration::from_secs(90 * DAY);
+        let now = 100 * DAY;
+        let used = KeyRecord {
+            created: Some(0),
+            last_used: Some(20 * DAY),
+        };
+        let never_used = KeyRecord {
+            created: Some(5 * DAY),
+            last_used: None,
+        };
+        assert!(!used.is_stale(now, unused_for));
+        assert!(never_used.is_stale(now, unused_for));
+        assert!(!KeyRecord::default().is_stale(now, unused_for));
+        assert_eq!(mask("sk_test_123"), "sk_t…");
+    }
+
+    #[test]
+    fn netrc_finds_machine_password() {
+        let netrc = "machine other.example login a password nope\n\
+                     machine api.example.com\n  login me\n  password s3cret\n";
+        assert_eq!(
+            netrc_password(netrc, "api.example.com"),
+            Some("s3cret".to_string())
+        );
+    }
+
+    #[test]
+    fn netrc_falls_back_to_default() {
+        let netrc = "machine other.example password nope\ndefault login me password fallback";
+        assert_eq!(
+            netrc_password(netrc, "api.example.com"),
+            Some("fallback".to_string())
+        );
+    }
+
+    #[test]
+    fn netrc_without_match() {
+        assert_eq!(
+            netrc_password("machine other.example password nope", "api"),
+            None
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use std::{fmt, str::FromStr};
+
+use color_eyre::eyre::{eyre, Report};
+
+/// A shareable reference to a view, e.g. `counter?pane=1`.
+///
+/// The part before `?` names the screen, the rest is a list of `key=value`
+/// parameters. Interpreting the parameters is up to the screen.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DeepLink {
+    pub screen: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl FromStr for DeepLink {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (screen, query) = s.split_once('?').unwrap_or((s, ""));
+        if screen.is_empty() {
+            return Err(eyre!("Deep link `{}` does not name a screen.", s));
+        }
+
+        let params = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+                _ => Err(eyre!(
+                    "Deep link parameter `{}` must have the form key=value.",
+                    pair
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            screen: screen.to_string(),
+            params,
+        })
+    }
+}
+
+impl fmt::Display for DeepLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.screen)?;
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            write!(f, "{}{}={}", separator, key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_screen_and_params() {
+        let link: DeepLink = "counter?pane=1&since=1h".parse().unwrap();
+        assert_eq!(link.screen, "counter");
+        assert_eq!(
+            link.params,
+            vec![
+                ("pane".to_string(), "1".to_string()),
+                ("since".to_string(), "1h".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn displays_as_parsed() {
+        for text in ["counter", "counter?pane=1&since=1h"] {
+            assert_eq!(text.parse::<DeepLink>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn screen_without_query() {
+        let link: DeepLink = "counter".parse().unwrap();
+        assert_eq!(link.screen, "counter");
+        assert!(link.params.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_links() {
+        assert!("?pane=1".parse::<DeepLink>().is_err());
+        assert!("counter?pane".parse::<DeepLink>().is_err());
+        assert!("counter?=1".parse::<DeepLink>().is_err());
+    }
+}
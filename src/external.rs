@@ -0,0 +1,117 @@
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::{
+    env,
+    fs::{self, DirBuilder, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::eyre::{eyre, WrapErr};
+
+/// An external program the TUI can hand the terminal over to.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Program {
+    Editor,
+    Pager,
+}
+
+impl Program {
+    /// The command line to run, from `$EDITOR`/`$PAGER` or a platform default.
+    fn command_line(self) -> String {
+        let (var, default) = match self {
+            Program::Editor if cfg!(windows) => ("EDITOR", "notepad"),
+            Program::Editor => ("EDITOR", "vi"),
+            Program::Pager if cfg!(windows) => ("PAGER", "more"),
+            Program::Pager => ("PAGER", "less"),
+        };
+        env::var(var)
+            .ok()
+            .filter(|cmd| !cmd.trim().is_empty())
+            .unwrap_or_else(|| default.to_string())
+    }
+}
+
+/// Write `contents` to a temp file, open it in `program` and wait for it to
+/// exit, returning the file's contents afterwards.
+///
+/// The caller is responsible for giving up the terminal before calling this.
+///
+/// # Errors
+///
+/// This function will return an error if the temp file cannot be written or
+/// read back, if the program cannot be started, or if it exits unsuccessfully.
+pub fn run_on_temp_file(program: Program, contents: &str) -> color_eyre::Result<String> {
+    let path = write_private_file(contents).wrap_err("Could not write temp file")?;
+
+    let command_line = program.command_line();
+    // `$EDITOR` may carry arguments, e.g. `code --wait`
+    let mut parts = command_line.split_whitespace();
+    let name = parts.next().unwrap_or_default();
+    let status = process::Command::new(name)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .wrap_err_with(|| format!("Could not start `{}`", command_line));
+
+    let result = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Could not read back temp file {}", path.display())),
+        Ok(status) => Err(eyre!("`{}` exited with {}", command_line, status)),
+        Err(err) => Err(err),
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::remove_dir_all(dir);
+    }
+    result
+}
+
+/// Write `contents` to a file only the user can read, in a new directory
+/// of its own under the shared temp dir, since it holds pane text. Creating
+/// both fails rather than following anything planted at their paths.
+fn write_private_file(contents: &str) -> io::Result<PathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.subsec_nanos());
+    let dir = env::temp_dir().join(format!("monika-{}-{}", process::id(), nanos));
+    let mut builder = DirBuilder::new();
+    #[cfg(unix)]
+    builder.mode(0o700);
+    builder.create(&dir)?;
+
+    let path = dir.join("monika.txt");
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let written = options
+        .open(&path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()));
+    match written {
+        Ok(()) => Ok(path),
+        Err(err) => {
+            let _ = fs::remove_dir_all(&dir);
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn temp_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = write_private_file("secret").unwrap();
+        let dir = path.parent().unwrap();
+        let mode = |path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!((mode(dir), mode(&path)), (0o700, 0o600));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "secret");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
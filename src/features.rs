@@ -0,0 +1,77 @@
+use color_eyre::eyre::eyre;
+
+use crate::config::Config;
+
+/// A subsystem that is optional, either at build time through a cargo
+/// feature or at runtime through an `[experimental]` config flag.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Feature {
+    /// Copying to the OS clipboard (cargo feature `system-clipboard`).
+    SystemClipboard,
+    /// Serving the TUI to a browser (`experimental.web_mirror`).
+    WebMirror,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 2] = [Feature::SystemClipboard, Feature::WebMirror];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::SystemClipboard => "system-clipboard",
+            Feature::WebMirror => "web_mirror",
+        }
+    }
+
+    /// Whether this build includes the feature.
+    pub fn compiled(self) -> bool {
+        match self {
+            Feature::SystemClipboard => cfg!(feature = "system-clipboard"),
+            Feature::WebMirror => true,
+        }
+    }
+
+    /// Whether the feature is still experimental and has to be opted into.
+    pub fn experimental(self) -> bool {
+        matches!(self, Feature::WebMirror)
+    }
+
+    pub fn enabled(self, config: &Config) -> bool {
+        let opted_in = match self {
+            Feature::SystemClipboard => true,
+            Feature::WebMirror => config.experimental.web_mirror,
+        };
+        self.compiled() && opted_in
+    }
+
+    /// How the feature stands, for `status` and `doctor`.
+    pub fn describe(self, config: &Config) -> &'static str {
+        match (self.compiled(), self.enabled(config), self.experimental()) {
+            (false, _, _) => "not built in",
+            (true, true, true) => "on (experimental)",
+            (true, true, false) => "on",
+            (true, false, _) => "off",
+        }
+    }
+
+    /// # Errors
+    ///
+    /// This function will return an error explaining how to turn the feature
+    /// on if it is not enabled.
+    pub fn require(self, config: &Config) -> color_eyre::Result<()> {
+        if !self.compiled() {
+            Err(eyre!(
+                "This build does not include {}. Rebuild with `--features {}`.",
+                self.name(),
+                self.name()
+            ))
+        } else if !self.enabled(config) {
+            Err(eyre!(
+                "{} is experimental. Enable it with `{} = true` under [experimental] in the config.",
+                self.name(),
+                self.name()
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
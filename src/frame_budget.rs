@@ -0,0 +1,113 @@
+use std::time::{Duration, Instant};
+
+/// Components listed for a slow frame, slowest first.
+const SLOWEST_SHOWN: usize = 3;
+
+/// Time spent drawing each component of one frame.
+#[derive(Debug, Default)]
+pub struct FrameTimings {
+    components: Vec<(&'static str, Duration)>,
+}
+
+impl FrameTimings {
+    /// Run `draw`, recording how long it took as component `name`.
+    pub fn measure<T>(&mut self, name: &'static str, draw: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let drawn = draw();
+        self.components.push((name, started.elapsed()));
+        drawn
+    }
+}
+
+/// A frame that took longer than the budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowFrame {
+    pub total: Duration,
+    /// The slowest components, slowest first.
+    pub slowest: Vec<(&'static str, Duration)>,
+}
+
+/// Draw times checked against a budget, for the debug screen.
+#[derive(Debug, Default, Clone)]
+pub struct FrameBudget {
+    pub budget: Duration,
+    pub frames: u64,
+    pub slow_frames: u64,
+    pub last_slow: Option<SlowFrame>,
+}
+
+impl FrameBudget {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            ..Self::default()
+        }
+    }
+
+    /// Count a frame that took `total`, keeping its `timings` if it was slow.
+    pub fn record(&mut self, total: Duration, mut timings: FrameTimings) {
+        self.frames += 1;
+        if total <= self.budget {
+            return;
+        }
+        self.slow_frames += 1;
+        timings
+            .components
+            .sort_by_key(|(_, took)| std::cmp::Reverse(*took));
+        timings.components.truncate(SLOWEST_SHOWN);
+        self.last_slow = Some(SlowFrame {
+            total,
+            slowest: timings.components,
+        });
+    }
+
+    /// A one-line summary, e.g. `2 of 340 frames over 16ms, last 23ms:
+    /// output 18ms, tasks 3ms`.
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "{} of {} frames over {}ms",
+            self.slow_frames,
+            self.frames,
+            self.budget.as_millis()
+        );
+        if let Some(slow) = &self.last_slow {
+            let components: Vec<String> = slow
+                .slowest
+                .iter()
+                .map(|(name, took)| format!("{} {}ms", name, took.as_millis()))
+                .collect();
+            text.push_str(&format!(
+                ", last {}ms: {}",
+                slow.total.as_millis(),
+                components.join(", ")
+            ));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_slowest_components_of_slow_frames() {
+        let mut budget = FrameBudget::new(Duration::from_millis(16));
+        budget.record(Duration::from_millis(5), FrameTimings::default());
+        assert_eq!(budget.describe(), "0 of 1 frames over 16ms");
+
+        let timings = FrameTimings {
+            components: vec![
+                ("counters", Duration::from_millis(1)),
+                ("output", Duration::from_millis(18)),
+                ("status", Duration::from_millis(2)),
+                ("tasks", Duration::from_millis(3)),
+            ],
+        };
+        budget.record(Duration::from_millis(24), timings);
+        assert_eq!(
+            budget.describe(),
+            "1 of 2 frames over 16ms, last 24ms: output 18ms, tasks 3ms, status 2ms"
+        );
+    }
+}
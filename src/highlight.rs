@@ -0,0 +1,142 @@
+use std::ops::Range;
+
+use ratatui::text::{Line, Span};
+
+use crate::theme::{Role, Theme};
+
+/// What a stretch of JSON text is.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Token {
+    Key,
+    String,
+    /// Numbers, `true`, `false` and `null`.
+    Literal,
+}
+
+impl Token {
+    fn role(self) -> Role {
+        match self {
+            Token::Key => Role::Info,
+            Token::String => Role::Success,
+            Token::Literal => Role::Accent,
+        }
+    }
+}
+
+/// The highlighted stretches of a line of JSON, by byte range.
+///
+/// Works line by line without a full parse, so it copes with pretty-printed
+/// documents and with output that merely contains some JSON.
+pub fn json_tokens(line: &str) -> Vec<(Range<usize>, Token)> {
+    let bytes = line.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                let rest = line[i..].trim_start();
+                let token = if rest.starts_with(':') {
+                    Token::Key
+                } else {
+                    Token::String
+                };
+                tokens.push((start..i, token));
+            }
+            b'-' | b'0'..=b'9' => {
+                i += 1;
+                while i < bytes.len()
+                    && matches!(bytes[i], b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-')
+                {
+                    i += 1;
+                }
+                tokens.push((start..i, Token::Literal));
+            }
+            b'a'..=b'z' => {
+                while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                if matches!(&line[start..i], "true" | "false" | "null") {
+                    tokens.push((start..i, Token::Literal));
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    tokens
+}
+
+/// The bytes `range` of `line` as JSON, colored with `theme`.
+pub fn json_line<'a>(line: &'a str, range: Range<usize>, theme: &Theme) -> Line<'a> {
+    styled(line, range, &json_tokens(line), theme)
+}
+
+/// The bytes `range` of `line`, colored by its `tokens` with `theme`.
+pub fn styled<'a>(
+    line: &'a str,
+    range: Range<usize>,
+    tokens: &[(Range<usize>, Token)],
+    theme: &Theme,
+) -> Line<'a> {
+    let mut spans = Vec::new();
+    let mut at = range.start;
+    for (token_range, token) in tokens {
+        let (start, end) = (
+            token_range.start.max(range.start),
+            token_range.end.min(range.end),
+        );
+        if start >= end {
+            continue;
+        }
+        if at < start {
+            spans.push(Span::raw(&line[at..start]));
+        }
+        spans.push(Span::styled(&line[start..end], theme.style(token.role())));
+        at = end;
+    }
+    if at < range.end {
+        spans.push(Span::raw(&line[at..range.end]));
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_json() {
+        let line = r#"  "name": "a \"b\"", "count": -1.5e3, "ok": true, "x": nul"#;
+        let tokens: Vec<(&str, Token)> = json_tokens(line)
+            .into_iter()
+            .map(|(range, token)| (&line[range], token))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (r#""name""#, Token::Key),
+                (r#""a \"b\"""#, Token::String),
+                (r#""count""#, Token::Key),
+                ("-1.5e3", Token::Literal),
+                (r#""ok""#, Token::Key),
+                ("true", Token::Literal),
+                (r#""x""#, Token::Key),
+            ]
+        );
+    }
+
+    #[test]
+    fn highlights_part_of_a_line() {
+        let theme = Theme::default();
+        let line = r#"{"key": "value"}"#;
+        // A row cutting the string in two keeps coloring its part
+        let row = json_line(line, 8..12, &theme);
+        assert_eq!(row.to_string(), "\"val");
+        assert_eq!(row.spans[0].style, theme.style(Role::Success));
+    }
+}
@@ -0,0 +1,78 @@
+use std::{io, time::Duration};
+
+#[cfg(test)]
+use std::collections::VecDeque;
+
+use ratatui::crossterm::event::{self, Event};
+
+/// Where the application loop gets its events from.
+pub trait EventSource {
+    /// The next event, waiting at most `timeout`; `None` if none arrived.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if reading input fails.
+    fn next(&mut self, timeout: Duration) -> io::Result<Option<Event>>;
+
+    /// Whether the events come from a real terminal, which then needs
+    /// looking after (raw mode, title, hyperlinks).
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+/// Events from the terminal the app runs in.
+pub struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn next(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        if event::poll(timeout)? {
+            event::read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        true
+    }
+}
+
+/// A fixed sequence of events, returned without waiting. Running out is an
+/// error rather than an endless wait, so a script that forgets to quit fails
+/// instead of hanging.
+#[cfg(test)]
+pub struct Scripted {
+    events: VecDeque<Option<Event>>,
+}
+
+#[cfg(test)]
+impl Scripted {
+    /// A script of events, where `None` stands for a poll timing out.
+    pub fn new(events: impl IntoIterator<Item = Option<Event>>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+        }
+    }
+
+    /// A script typing `keys`, one key press per character.
+    pub fn keys(keys: &str) -> Self {
+        use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        Self::new(keys.chars().map(|c| {
+            Some(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                KeyModifiers::NONE,
+            )))
+        }))
+    }
+}
+
+#[cfg(test)]
+impl EventSource for Scripted {
+    fn next(&mut self, _: Duration) -> io::Result<Option<Event>> {
+        self.events
+            .pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "event script ended"))
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+/// A visible row of the tree.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Node {
+    pub depth: usize,
+    /// JSON pointer to the value, e.g. `/items/0/name`.
+    pub pointer: String,
+    /// Path to the value in jq syntax, e.g. `.items[0].name`.
+    pub path: String,
+    /// Object key or array index, empty for the root.
+    pub label: String,
+    /// The value itself for scalars, its size for objects and arrays.
+    pub summary: String,
+    pub expandable: bool,
+    pub expanded: bool,
+}
+
+/// A JSON document explored as a collapsible tree.
+#[derive(Debug, Clone)]
+pub struct JsonTree {
+    root: Value,
+    /// Pointers of the expanded objects and arrays.
+    expanded: BTreeSet<String>,
+    /// Index into the visible nodes.
+    pub selected: usize,
+    /// Search text being typed, if searching.
+    pub search: Option<String>,
+    /// The last search, repeated by `next_match`.
+    pub last_search: String,
+}
+
+impl JsonTree {
+    /// Parse `text` as a JSON document, with the top level expanded.
+    pub fn parse(text: &str) -> Option<Self> {
+        let root: Value = serde_json::from_str(text).ok()?;
+        // Bare scalars are better read as they are
+        if !(root.is_object() || root.is_array()) {
+            return None;
+        }
+        Some(Self {
+            root,
+            expanded: BTreeSet::from([String::new()]),
+            selected: 0,
+            search: None,
+            last_search: String::new(),
+        })
+    }
+
+    /// The nodes currently shown, in document order.
+    pub fn nodes(&self) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let root = Node::root();
+        flatten(
+            &self.root,
+            root,
+            &mut |pointer| self.expanded.contains(pointer),
+            &mut nodes,
+        );
+        nodes
+    }
+
+    pub fn select(&mut self, delta: isize) {
+        let last = self.nodes().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Expand the selected node if collapsed, collapse it if expanded.
+    pub fn toggle(&mut self) {
+        let Some(node) = self.nodes().into_iter().nth(self.selected) else {
+            return;
+        };
+        if !node.expandable {
+            return;
+        }
+        if !self.expanded.remove(&node.pointer) {
+            self.expanded.insert(node.pointer);
+        }
+    }
+
+    /// The selected node's path in jq syntax, e.g. `.items[0].name`.
+    pub fn selected_path(&self) -> Option<String> {
+        let node = self.nodes().into_iter().nth(self.selected)?;
+        Some(match node.path.as_str() {
+            "" => ".".to_string(),
+            _ => node.path,
+        })
+    }
+
+    /// The selected value as pretty-printed JSON.
+    pub fn selected_value(&self) -> Option<String> {
+        let node = self.nodes().into_iter().nth(self.selected)?;
+        let value = self.root.pointer(&node.pointer)?;
+        serde_json::to_string_pretty(value).ok()
+    }
+
+    /// Select the next node after the selection whose key or value contains
+    /// `last_search`, expanding its parents. Returns whether one was found.
+    pub fn next_match(&mut self) -> bool {
+        let query = self.last_search.to_lowercase();
+        if query.is_empty() {
+            return false;
+        }
+        let mut all = Vec::new();
+        flatten(&self.root, Node::root(), &mut |_| true, &mut all);
+        let current = self
+            .nodes()
+            .into_iter()
+            .nth(self.selected)
+            .and_then(|node| all.iter().position(|n| n.pointer == node.pointer))
+            .unwrap_or(0);
+        let is_match = |node: &Node| {
+            node.label.to_lowercase().contains(&query)
+                || (!node.expandable && node.summary.to_lowercase().contains(&query))
+        };
+        // Search forward from the selection, wrapping around
+        let found = all[current + 1..]
+            .iter()
+            .chain(&all[..=current.min(all.len() - 1)])
+            .find(|node| is_match(node));
+        let Some(found) = found else {
+            return false;
+        };
+        let pointer = found.pointer.clone();
+        let mut parent = String::new();
+        self.expanded.insert(parent.clone());
+        for part in pointer.split('/').skip(1) {
+            parent = format!("{}/{}", parent, part);
+            if parent != pointer {
+                self.expanded.insert(parent.clone());
+            }
+        }
+        self.selected = self
+            .nodes()
+            .iter()
+            .position(|node| node.pointer == pointer)
+            .unwrap_or(0);
+        true
+    }
+}
+
+impl Node {
+    fn root() -> Self {
+        Self {
+            depth: 0,
+            pointer: String::new(),
+            path: String::new(),
+            label: String::new(),
+            summary: String::new(),
+            expandable: false,
+            expanded: false,
+        }
+    }
+
+    fn child(&self, key: String, path: String) -> Self {
+        Self {
+            depth: self.depth + 1,
+            pointer: format!(
+                "{}/{}",
+                self.pointer,
+                key.replace('~', "~0").replace('/', "~1")
+            ),
+            path: format!("{}{}", self.path, path),
+            label: key,
+            ..Self::root()
+        }
+    }
+}
+
+/// Push `node`, describing `value`, and its children where `expanded`.
+fn flatten(
+    value: &Value,
+    mut node: Node,
+    expanded: &mut impl FnMut(&str) -> bool,
+    nodes: &mut Vec<Node>,
+) {
+    (node.summary, node.expandable) = match value {
+        Value::Object(map) => (format!("{{{}}}", map.len()), true),
+        Value::Array(items) => (format!("[{}]", items.len()), true),
+        scalar => (scalar.to_string(), false),
+    };
+    node.expanded = node.expandable && expanded(&node.pointer);
+    let children: Vec<(Node, &Value)> = match value {
+        Value::Object(map) if node.expanded => map
+            .iter()
+            .map(|(key, value)| (node.child(key.clone(), jq_key(key)), value))
+            .collect(),
+        Value::Array(items) if node.expanded => items
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (node.child(i.to_string(), format!("[{}]", i)), value))
+            .collect(),
+        _ => Vec::new(),
+    };
+    nodes.push(node);
+    for (child, value) in children {
+        flatten(value, child, expanded, nodes);
+    }
+}
+
+/// How `key` is written in a jq path, e.g. `.name` or `["my key"]`.
+fn jq_key(key: &str) -> String {
+    let identifier = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if identifier {
+        format!(".{}", key)
+    } else {
+        format!("[{}]", Value::String(key.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str =
+        r#"{"status": "ok", "items": [{"name": "api", "my key": 1}, {"name": "web"}]}"#;
+
+    fn labels(tree: &JsonTree) -> Vec<String> {
+        tree.nodes().into_iter().map(|node| node.label).collect()
+    }
+
+    #[test]
+    fn expands_and_collapses() {
+        let mut tree = JsonTree::parse(DOCUMENT).unwrap();
+        assert_eq!(labels(&tree), vec!["", "items", "status"]);
+
+        tree.select(1);
+        tree.toggle();
+        assert_eq!(labels(&tree), vec!["", "items", "0", "1", "status"]);
+        assert_eq!(tree.selected_path().as_deref(), Some(".items"));
+
+        tree.toggle();
+        assert_eq!(labels(&tree), vec!["", "items", "status"]);
+        assert!(JsonTree::parse("42").is_none());
+    }
+
+    #[test]
+    fn search_reveals_matches() {
+        let mut tree = JsonTree::parse(DOCUMENT).unwrap();
+        tree.last_search = "WEB".to_string();
+        assert!(tree.next_match());
+        assert_eq!(tree.selected_path().as_deref(), Some(".items[1].name"));
+        assert_eq!(tree.selected_value().as_deref(), Some("\"web\""));
+
+        tree.last_search = "my key".to_string();
+        assert!(tree.next_match());
+        assert_eq!(
+            tree.selected_path().as_deref(),
+            Some(".items[0][\"my key\"]")
+        );
+
+        tree.last_search = "missing".to_string();
+        assert!(!tree.next_match());
+    }
+}
@@ -0,0 +1,319 @@
+use std::fmt;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{capabilities::Capabilities, config::Config, quick_command::QuickCommand};
+
+/// The keys of the main screen and what they do, as matched by
+/// `handle_key`, besides the number keys. Keys without modifiers match
+/// whatever modifiers are held.
+const BUILTIN: [(&str, &str); 24] = [
+    ("ctrl-l", "redraw"),
+    ("j", "increment"),
+    ("k", "decrement"),
+    ("r", "refresh"),
+    ("e", "edit"),
+    ("v", "view in pager"),
+    ("y", "copy"),
+    ("o", "open link"),
+    ("s", "share"),
+    ("n", "notifications"),
+    ("esc", "dismiss"),
+    ("tab", "next pane"),
+    ("pageup", "scroll up"),
+    ("pagedown", "scroll down"),
+    ("w", "wrap"),
+    ("t", "JSON tree"),
+    ("D", "tasks"),
+    ("T", "timestamps"),
+    ("m", "context menu"),
+    ("h", "pan left"),
+    ("left", "pan left"),
+    ("l", "pan right"),
+    ("right", "pan right"),
+    ("q", "quit"),
+];
+
+/// Number keys run the quick command at their position.
+const NUMBER_KEYS: usize = 9;
+
+/// A key with its modifiers, e.g. `ctrl-g` or `G`.
+///
+/// Shift is folded into letters, so `shift-g` and `G` are the same chord.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Chord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Chord {
+    /// Parse a chord like `g`, `ctrl-g`, `alt-shift-left` or `f5`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (modifiers, key) = match text.rsplit_once('-') {
+            // `-` and `ctrl--` name the minus key itself
+            Some(("", "")) => ("", "-"),
+            Some((modifiers, "")) => match modifiers.strip_suffix('-') {
+                Some(modifiers) => (modifiers, "-"),
+                None => return Err("missing key".to_string()),
+            },
+            Some((modifiers, key)) => (modifiers, key),
+            None => ("", text),
+        };
+        let mut chord = Self {
+            code: parse_code(key).ok_or_else(|| format!("unknown key `{}`", key))?,
+            modifiers: KeyModifiers::NONE,
+        };
+        for modifier in modifiers.split('-').filter(|m| !m.is_empty()) {
+            chord.modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                "super" => KeyModifiers::SUPER,
+                "hyper" => KeyModifiers::HYPER,
+                "meta" => KeyModifiers::META,
+                _ => return Err(format!("unknown modifier `{}`", modifier)),
+            };
+        }
+        Ok(chord.normalized())
+    }
+
+    fn normalized(mut self) -> Self {
+        if let KeyCode::Char(c) = self.code {
+            if self.modifiers.contains(KeyModifiers::SHIFT) || c.is_uppercase() {
+                self.code = KeyCode::Char(c.to_ascii_uppercase());
+                self.modifiers.remove(KeyModifiers::SHIFT);
+            }
+        }
+        self
+    }
+
+    /// Whether a terminal can only report this chord with the kitty keyboard
+    /// protocol. Legacy terminals send the same bytes for e.g. `ctrl-i` and
+    /// `tab`, and have no way to send super, hyper or meta at all.
+    fn needs_kitty_keyboard(&self) -> bool {
+        let ctrl = self.modifiers.contains(KeyModifiers::CONTROL);
+        let extended = KeyModifiers::SUPER | KeyModifiers::HYPER | KeyModifiers::META;
+        self.modifiers.intersects(extended)
+            || (ctrl && self.modifiers.contains(KeyModifiers::SHIFT))
+            || match self.code {
+                KeyCode::Char(c) if ctrl => {
+                    c.is_ascii_uppercase() || c.is_ascii_digit() || "imh[".contains(c)
+                }
+                _ => false,
+            }
+    }
+
+    /// Whether pressing `self` triggers the built-in binding `builtin`.
+    fn triggers(&self, builtin: &Chord) -> bool {
+        self.code == builtin.code
+            && (builtin.modifiers.is_empty() || self.modifiers == builtin.modifiers)
+    }
+}
+
+impl From<KeyEvent> for Chord {
+    fn from(key: KeyEvent) -> Self {
+        Self {
+            code: key.code,
+            modifiers: key.modifiers,
+        }
+        .normalized()
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+            (KeyModifiers::SUPER, "super-"),
+            (KeyModifiers::HYPER, "hyper-"),
+            (KeyModifiers::META, "meta-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => f.write_str(&format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+fn parse_code(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    Some(match key.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        f => KeyCode::F(
+            f.strip_prefix('f')?
+                .parse()
+                .ok()
+                .filter(|n| (1..=12).contains(n))?,
+        ),
+    })
+}
+
+/// The keys given to quick commands, with the names of their commands.
+/// Keys that do not parse are left out; `lint` reports them.
+pub fn quick_command_keys(commands: &[QuickCommand]) -> Vec<(Chord, String)> {
+    commands
+        .iter()
+        .filter_map(|command| {
+            let chord = Chord::parse(command.key.as_deref()?).ok()?;
+            Some((chord, command.name.clone()))
+        })
+        .collect()
+}
+
+/// Every key of the main screen with what it does: the built-in keys, then
+/// the keys of the quick commands in `commands`.
+pub fn bindings(commands: &[QuickCommand]) -> Vec<(String, String)> {
+    let builtin = BUILTIN
+        .iter()
+        .map(|(key, action)| (key.to_string(), action.to_string()));
+    let numbers = commands
+        .iter()
+        .take(NUMBER_KEYS)
+        .enumerate()
+        .map(|(index, command)| ((index + 1).to_string(), format!("run `{}`", command.name)));
+    let keys = quick_command_keys(commands)
+        .into_iter()
+        .map(|(chord, name)| (chord.to_string(), format!("run `{}`", name)));
+    builtin.chain(numbers).chain(keys).collect()
+}
+
+/// Problems with the key bindings of `config` on a terminal with
+/// `capabilities`, each saying how to fix it.
+pub fn lint(config: &Config, capabilities: &Capabilities) -> Vec<String> {
+    let builtin: Vec<(Chord, &str)> = BUILTIN
+        .iter()
+        .map(|(key, action)| (Chord::parse(key).expect("built-in keys parse"), *action))
+        .collect();
+    let mut problems = Vec::new();
+    let mut bound: Vec<(Chord, &str)> = Vec::new();
+
+    for (index, command) in config.quick_commands.iter().enumerate() {
+        let name = command.name.as_str();
+        let Some(key) = &command.key else {
+            if index >= NUMBER_KEYS {
+                problems.push(format!(
+                    "Quick command `{}` has no key: only the first {} get number keys. \
+                     Give it a `key`.",
+                    name, NUMBER_KEYS
+                ));
+            }
+            continue;
+        };
+        let chord = match Chord::parse(key) {
+            Ok(chord) => chord,
+            Err(err) => {
+                problems.push(format!(
+                    "Quick command `{}` has an invalid key `{}`: {}. \
+                     Use e.g. `g`, `ctrl-g` or `f5`.",
+                    name, key, err
+                ));
+                continue;
+            }
+        };
+
+        if let Some((_, action)) = builtin.iter().find(|(b, _)| chord.triggers(b)) {
+            problems.push(format!(
+                "`{}` for quick command `{}` is shadowed by the built-in `{}`. \
+                 Pick another key.",
+                chord, name, action
+            ));
+        } else if matches!(chord.code, KeyCode::Char('1'..='9')) {
+            problems.push(format!(
+                "`{}` for quick command `{}` is shadowed by the quick command number keys. \
+                 Pick another key.",
+                chord, name
+            ));
+        } else if let Some((_, first)) = bound.iter().find(|(b, _)| *b == chord) {
+            problems.push(format!(
+                "`{}` is bound to both `{}` and `{}`; only `{}` runs. \
+                 Change one of the keys.",
+                chord, first, name, first
+            ));
+        } else if chord.needs_kitty_keyboard() && !capabilities.kitty_keyboard {
+            problems.push(format!(
+                "`{}` for quick command `{}` cannot be told apart by this terminal, \
+                 which lacks the kitty keyboard protocol. Pick a plainer key.",
+                chord, name
+            ));
+        }
+        bound.push((chord, name));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(name: &str, key: Option<&str>) -> QuickCommand {
+        QuickCommand {
+            name: name.to_string(),
+            program: "true".to_string(),
+            args: Vec::new(),
+            key: key.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parses_chords() {
+        let chord = Chord::parse("ctrl-shift-g").unwrap();
+        assert_eq!(chord.code, KeyCode::Char('G'));
+        assert_eq!(chord.modifiers, KeyModifiers::CONTROL);
+        assert_eq!(chord, Chord::parse("ctrl-G").unwrap());
+        assert_eq!(Chord::parse("alt--").unwrap().to_string(), "alt--");
+        assert_eq!(Chord::parse("f5").unwrap().to_string(), "f5");
+        assert!(Chord::parse("cmd-g").is_err());
+        assert!(Chord::parse("f13").is_err());
+    }
+
+    #[test]
+    fn lints_bindings() {
+        let config = Config {
+            quick_commands: vec![
+                command("deploy", Some("g")),
+                command("logs", Some("g")),
+                command("build", Some("alt-j")),
+                command("test", Some("ctrl-i")),
+                command("lint", Some("ctrl-")),
+            ],
+            ..Config::default()
+        };
+        let problems = lint(&config, &Capabilities::default());
+        assert_eq!(problems.len(), 4);
+        assert!(problems[0].starts_with("`g` is bound to both `deploy` and `logs`"));
+        assert!(problems[1].contains("shadowed by the built-in `increment`"));
+        assert!(problems[2].contains("kitty keyboard protocol"));
+        assert!(problems[3].contains("invalid key `ctrl-`"));
+
+        let kitty = Capabilities {
+            kitty_keyboard: true,
+            ..Capabilities::default()
+        };
+        assert_eq!(lint(&config, &kitty).len(), 3);
+    }
+}
@@ -0,0 +1,78 @@
+use std::env;
+
+/// A URL drawn on screen, positioned by its first cell.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Link {
+    pub x: u16,
+    pub y: u16,
+    pub url: String,
+}
+
+/// The `http(s)://` URLs in `text` with their byte offsets.
+///
+/// A URL runs until whitespace or a control character, which must never
+/// end up inside the escape sequence of a hyperlink. Trailing punctuation is
+/// left out so that "see https://example.com." links to the site, not to
+/// "example.com.".
+pub fn find_urls(text: &str) -> Vec<(usize, &str)> {
+    let mut urls = Vec::new();
+    let mut rest = 0;
+    while let Some(found) = ["https://", "http://"]
+        .iter()
+        .filter_map(|scheme| text[rest..].find(scheme))
+        .min()
+    {
+        let start = rest + found;
+        let end = text[start..]
+            .find(|c: char| c.is_whitespace() || c.is_control())
+            .map_or(text.len(), |len| start + len);
+        let url = text[start..end].trim_end_matches(['.', ',', ';', ':', ')', ']', '\'', '"']);
+        if !url.ends_with("//") {
+            urls.push((start, url));
+        }
+        rest = end;
+    }
+    urls
+}
+
+/// Whether the terminal is known to render OSC 8 hyperlinks.
+///
+/// There is no reliable way to ask, so this goes by what terminals announce
+/// about themselves; unknown terminals get the "press o to open" fallback.
+pub fn supported() -> bool {
+    let var = |name| env::var(name).unwrap_or_default();
+    matches!(
+        var("TERM_PROGRAM").as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+    ) || env::var_os("KITTY_WINDOW_ID").is_some()
+        || env::var_os("WT_SESSION").is_some()
+        || var("VTE_VERSION").parse::<u32>().is_ok_and(|v| v >= 5000)
+        || var("TERM").starts_with("foot")
+}
+
+/// `text` wrapped in an OSC 8 hyperlink to `url`.
+pub fn osc8(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_urls() {
+        assert_eq!(
+            find_urls("docs at https://example.com/a?b=c, or http://x.org."),
+            vec![(8, "https://example.com/a?b=c"), (38, "http://x.org")]
+        );
+        assert_eq!(
+            find_urls("(https://example.com)"),
+            vec![(1, "https://example.com")]
+        );
+        assert!(find_urls("no links, just https:// here").is_empty());
+        assert_eq!(
+            find_urls("https://x.org\x1b]52;c;aGk=\x07"),
+            vec![(0, "https://x.org")]
+        );
+    }
+}
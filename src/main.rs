@@ -1,70 +1,658 @@
 mod application;
-use clap::{arg, command, Command};
-use color_eyre::eyre::eyre;
-use keyring::Entry;
+mod bell;
+mod bundle;
+mod capabilities;
+mod clipboard;
+mod config;
+mod context_menu;
+mod credentials;
+mod deep_link;
+mod external;
+mod features;
+mod frame_budget;
+mod highlight;
+mod input;
+mod json_tree;
+mod keymap;
+mod links;
+mod migrate;
+mod mirror;
+mod notifications;
+mod pane;
+mod persist;
+mod policy;
+mod prepare;
+mod qr;
+mod quick_command;
+mod remote_config;
+mod render;
+mod render_cache;
+mod rules;
+mod scroll_view;
+mod snapshot;
+mod tasks;
+mod theme;
+mod timestamp;
+mod toast;
+mod trend;
+mod tutorial;
+mod validate;
+mod whats_new;
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, IsTerminal},
+    net::SocketAddr,
+    path::{self, Path, PathBuf},
+    process,
+    time::Duration,
+};
+
+use clap::{arg, command, value_parser, ArgGroup, Command};
+use color_eyre::eyre::{eyre, WrapErr};
+use ratatui::crossterm::terminal;
+
+use crate::{
+    capabilities::Capabilities,
+    config::{Config, ProfileConfig},
+    deep_link::DeepLink,
+    features::Feature,
+    mirror::Mirror,
+    snapshot::Snapshot,
+};
 
 /// Given an API key, store it in the keychain.
 ///
 /// # Errors
 ///
-/// This function will return an error if the key is not the correct format.
-/// Also if the API key couldn't be stored in the keychain.
-fn register_api_key(api_key: &str) -> color_eyre::Result<()> {
-    println!("Registering API key: {}", api_key);
-
-    let entry = Entry::new("monika-cli", "api_key")?;
+/// This function will return an error if the key is not the correct format,
+/// if it couldn't be stored in the keychain, or if it couldn't be read back.
+fn register_api_key(api_key: &str, profile: &str) -> color_eyre::Result<()> {
+    let pasted = credentials::normalize_key(api_key);
+    if !pasted.invisible.is_empty() {
+        let codes: Vec<String> = pasted
+            .invisible
+            .iter()
+            .map(|c| format!("U+{:04X}", u32::from(*c)))
+            .collect();
+        eprintln!(
+            "Warning: removed invisible characters from the key ({}). \
+            Check that it was copied from the right place.",
+            codes.join(", ")
+        );
+    }
+    let api_key = pasted.key.as_str();
+    println!("Registering API key: {}", credentials::mask(api_key));
 
-    let api_key_expected_len = application::API_KEY_LEN;
+    credentials::store_key(profile, api_key)?;
+    println!("API key successfully stored.");
+    Ok(())
+}
 
-    if api_key.len() == api_key_expected_len {
-        entry.set_password(api_key)?;
-        println!("API key successfully stored.");
+/// Remove the API key of `profile` from the keychain, or of every profile
+/// with `all`.
+///
+/// # Errors
+///
+/// This function will return an error if the keychain cannot be accessed, or
+/// with `all` if the configuration cannot be loaded.
+fn logout(profile: &str, all: bool) -> color_eyre::Result<()> {
+    let profiles = if all {
+        known_profiles(&Config::load_local()?)
     } else {
+        BTreeSet::from([profile.to_string()])
+    };
+    let mut removed = 0;
+    for profile in &profiles {
+        if credentials::delete_key(profile).map_err(credentials::keyring_error)? {
+            println!("Removed the API key of profile `{}`.", profile);
+            removed += 1;
+        }
+    }
+    if removed == 0 {
+        println!("No API key was stored.");
+    }
+    Ok(())
+}
+
+/// Profiles that may have a key in the keychain: the default one, those in
+/// the config, and those `login` recorded.
+fn known_profiles(config: &Config) -> BTreeSet<String> {
+    let mut profiles = BTreeSet::from(["default".to_string()]);
+    profiles.extend(config.profiles.keys().cloned());
+    profiles.extend(credentials::ledger().into_keys());
+    profiles
+}
+
+/// List the keys stored in the keychain, masked, with when they were stored
+/// and last used.
+///
+/// # Errors
+///
+/// This function will return an error if the configuration cannot be loaded
+/// or the keychain cannot be accessed.
+fn list_credentials() -> color_eyre::Result<()> {
+    let ledger = credentials::ledger();
+    let now = credentials::now();
+    let age = |at: Option<u64>, unknown: &str| match at {
+        Some(at) => timestamp::format_age(Duration::from_secs(now.saturating_sub(at))),
+        None => unknown.to_string(),
+    };
+    let mut found = false;
+    for profile in known_profiles(&Config::load_local()?) {
+        let Some(api_key) =
+            credentials::stored_key(&profile).map_err(credentials::keyring_error)?
+        else {
+            continue;
+        };
+        let record = ledger.get(&profile).copied().unwrap_or_default();
+        println!(
+            "{:<16} {:<8} stored {:<10} last used {}",
+            profile,
+            credentials::mask(&api_key),
+            age(record.created, "unknown"),
+            age(record.last_used, "never"),
+        );
+        found = true;
+    }
+    if !found {
+        println!("No API keys are stored.");
+    }
+    Ok(())
+}
+
+/// Remove keys that were not used for `unused_for`. Keys stored before their
+/// use was tracked are left alone.
+///
+/// # Errors
+///
+/// This function will return an error if the keychain cannot be accessed.
+fn prune_credentials(unused_for: Duration, dry_run: bool) -> color_eyre::Result<()> {
+    let now = credentials::now();
+    let stale: Vec<String> = credentials::ledger()
+        .into_iter()
+        .filter(|(_, record)| record.is_stale(now, unused_for))
+        .map(|(profile, _)| profile)
+        .collect();
+    for profile in &stale {
+        if dry_run {
+            println!("Would remove the API key of profile `{}`.", profile);
+        } else if credentials::delete_key(profile).map_err(credentials::keyring_error)? {
+            println!("Removed the API key of profile `{}`.", profile);
+        }
+    }
+    if stale.is_empty() {
+        println!("No unused API keys.");
+    }
+    Ok(())
+}
+
+/// Move an API key exported by the shell into the keychain, then point out
+/// the lines that export it.
+///
+/// # Errors
+///
+/// This function will return an error if different keys are exported, if
+/// the user declines, or if the key cannot be stored and verified.
+fn migrate_env(profile: &str, yes: bool) -> color_eyre::Result<()> {
+    let found = migrate::find_api_keys();
+    let Some(first) = found.first() else {
+        println!(
+            "{} is not set in the environment or in a shell startup file.",
+            credentials::API_KEY_ENV
+        );
+        return Ok(());
+    };
+    if found.iter().any(|other| other.api_key != first.api_key) {
+        for found in &found {
+            eprintln!("  {}", found.describe());
+        }
         return Err(eyre!(
-            "API key stored, but it is the wrong length. \
-            Expected {} bytes, found {} bytes.",
-            api_key_expected_len,
-            api_key.len()
+            "Found different API keys. Store the right one with `monika login`."
         ));
     }
 
+    println!("Found {}:", credentials::API_KEY_ENV);
+    for found in &found {
+        println!("  {}", found.describe());
+    }
+    if !yes {
+        if !io::stdin().is_terminal() {
+            return Err(eyre!("Run with --yes to store the key without asking."));
+        }
+        eprint!("Store it in the keychain for profile `{}`? [y/N] ", profile);
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            return Err(eyre!("Nothing was stored."));
+        }
+    }
+
+    credentials::store_key(profile, &first.api_key)?;
+    println!("API key stored and verified.");
+    let lines: Vec<&migrate::Found> = found
+        .iter()
+        .filter(|found| found.location.is_some())
+        .collect();
+    if lines.is_empty() {
+        println!(
+            "Stop exporting {} wherever it is set, then open a new shell.",
+            credentials::API_KEY_ENV
+        );
+    } else {
+        println!("Remove these lines, then open a new shell:");
+        for found in lines {
+            println!("  {}", found.describe());
+        }
+    }
     Ok(())
 }
 
+/// Read an API key pasted into stdin, however many lines it spans.
+///
+/// # Errors
+///
+/// This function will return an error if stdin cannot be read.
+fn read_pasted_key() -> color_eyre::Result<String> {
+    if io::stdin().is_terminal() {
+        eprintln!("Paste the API key, then press Ctrl-D:");
+    }
+    io::read_to_string(io::stdin()).wrap_err("Could not read the API key from stdin")
+}
+
+/// Find the API key of `profile` and check its length.
+///
+/// # Errors
+///
+/// This function will return an error if no credential source has an API key
+/// for `profile` or if it is the wrong length.
+fn load_api_key(
+    profile: &str,
+    settings: &ProfileConfig,
+) -> color_eyre::Result<[u8; application::API_KEY_LEN]> {
+    let api_key = credentials::resolve(profile, settings)?;
+
+    if api_key.len() == application::API_KEY_LEN {
+        Ok(api_key.as_bytes().try_into()?)
+    } else {
+        // return
+        Err(eyre!(
+            "API key found, but it is the wrong length. \
+            Expected {} bytes, found {} bytes.",
+            application::API_KEY_LEN,
+            api_key.len()
+        ))
+    }
+}
+
 /// Validate the configuration and run the application loop.
 ///
 /// # Errors
 ///
-/// This function will return an error if the API key is not found or if it is
-/// the wrong length. Also if the application loop panics.
-fn validate_config_and_run() -> color_eyre::Result<()> {
-    let entry = Entry::new("monika-cli", "api_key")?;
-    let api_key = entry.get_password();
+/// This function will return an error if the API key cannot be loaded, if
+/// `goto` is not a valid deep link, if the snapshot cannot be read, or if the
+/// web mirror cannot be served. Also if the application loop panics.
+fn validate_config_and_run(
+    profile: &str,
+    goto: Option<&String>,
+    mirror: Option<&SocketAddr>,
+    from_snapshot: Option<&String>,
+    quiet: bool,
+    safe_mode: bool,
+) -> color_eyre::Result<()> {
+    // A snapshot replaces the local config and the terminal probe
+    let (config, capabilities) = match from_snapshot {
+        Some(path) => {
+            let snapshot = Snapshot::read(Path::new(path))?;
+            let config = Config::from_snapshot(snapshot.config)?;
+            (config, Some(snapshot.terminal.capabilities))
+        }
+        None if safe_mode => {
+            persist::set_read_only();
+            (Config::load_safe()?, None)
+        }
+        None => (Config::load()?, None),
+    };
+    let goto = goto.map(|link| link.parse::<DeepLink>()).transpose()?;
+    if mirror.is_some() {
+        Feature::WebMirror.require(&config)?;
+    }
+    let mirror = mirror.map(|addr| Mirror::serve(*addr)).transpose()?;
+
+    // The keychain may block on an unlock prompt, so the TUI looks it up in
+    // the background. A replayed snapshot gets a placeholder key instead.
+    let replay = from_snapshot.is_some();
+    let (name, settings) = (profile.to_string(), config.profile(profile));
+    let api_key = move || {
+        if replay {
+            Ok([0; application::API_KEY_LEN])
+        } else {
+            load_api_key(&name, &settings)
+        }
+    };
 
-    match api_key {
-        Ok(api_key) => {
-            if api_key.len() == application::API_KEY_LEN {
-                let api_key_bytes: [u8; application::API_KEY_LEN] =
-                    api_key.as_bytes().try_into()?;
+    let summary =
+        application::application_loop(api_key, profile, config, goto, mirror, capabilities, false)?;
+    if !quiet {
+        println!("{}", summary);
+    }
+    Ok(())
+}
 
-                application::application_loop(api_key_bytes)
+/// Walk through the UI step by step. No API key is needed, so this works
+/// before `login`.
+///
+/// # Errors
+///
+/// This function will return an error if the configuration cannot be loaded
+/// or drawing to the terminal fails.
+fn run_tutorial(profile: &str) -> color_eyre::Result<()> {
+    let config = Config::load()?;
+    let api_key = || Ok([0; application::API_KEY_LEN]);
+    application::application_loop(api_key, profile, config, None, None, None, true)?;
+    Ok(())
+}
+
+/// Render `screen` once and print it to stdout, without taking over the
+/// terminal.
+///
+/// # Errors
+///
+/// This function will return an error if the API key cannot be loaded, or if
+/// `screen` is not a valid deep link.
+fn show_screen(
+    profile: &str,
+    screen: &str,
+    (width, height): (u16, u16),
+    color: &str,
+) -> color_eyre::Result<()> {
+    let config = Config::load()?;
+    let screen = screen.parse::<DeepLink>()?;
+    let api_key = load_api_key(profile, &config.profile(profile))?;
+
+    let buffer = application::render_once(api_key, config, &screen, width, height)?;
+    let ansi = match color {
+        "always" => true,
+        "never" => false,
+        _ => io::stdout().is_terminal(),
+    };
+    if ansi {
+        print!("{}", render::to_ansi(&buffer));
+    } else {
+        println!("{}", render::to_plain(&buffer));
+    }
+    Ok(())
+}
+
+/// Render the dashboard into standalone HTML and/or SVG files for sharing.
+///
+/// # Errors
+///
+/// This function will return an error if the API key cannot be loaded or an
+/// output file cannot be written.
+fn export_dashboard(
+    profile: &str,
+    html: Option<&String>,
+    svg: Option<&String>,
+) -> color_eyre::Result<()> {
+    let config = Config::load()?;
+    let api_key = load_api_key(profile, &config.profile(profile))?;
+    let screen = "counter".parse::<DeepLink>()?;
+
+    let buffer = application::render_once(api_key, config, &screen, 100, 30)?;
+    if let Some(path) = html {
+        persist::atomic_write(Path::new(path), render::to_html(&buffer))
+            .wrap_err_with(|| format!("Could not write {}", path))?;
+        println!("Dashboard written to {}.", path);
+    }
+    if let Some(path) = svg {
+        persist::atomic_write(Path::new(path), render::to_svg(&buffer))
+            .wrap_err_with(|| format!("Could not write {}", path))?;
+        println!("Dashboard written to {}.", path);
+    }
+    Ok(())
+}
+
+/// Run `command` with the API key of `profile` in its environment, then exit
+/// with the child's exit code.
+///
+/// The key is only set on the child; this process's own environment never
+/// holds it. Its copy in this process's memory is not zeroed, but lives only
+/// until the child exits.
+///
+/// # Errors
+///
+/// This function will return an error if no API key is found for the profile
+/// or if the command cannot be started.
+fn exec_with_api_key(profile: &str, command: &[&String]) -> color_eyre::Result<()> {
+    let config = Config::load_local()?;
+    let api_key = credentials::resolve(profile, &config.profile(profile))?;
+
+    let (program, args) = command.split_first().expect("COMMAND is required");
+    let status = process::Command::new(program)
+        .args(args)
+        .env(credentials::API_KEY_ENV, &api_key)
+        .status()
+        .wrap_err_with(|| format!("Could not run `{}`", program))?;
+
+    // Mirror the child's exit code; a signal-terminated child counts as failure
+    process::exit(status.code().unwrap_or(1));
+}
+
+/// Verify a signed profile bundle and add it to the config layers.
+///
+/// # Errors
+///
+/// This function will return an error if the bundle does not verify against
+/// the trusted signers in the config, or cannot be stored.
+fn import_bundle(path: &Path) -> color_eyre::Result<()> {
+    let config = Config::load_local()?;
+    let bundle = bundle::import(path, &config.trusted_signers)?;
+    println!(
+        "Imported bundle `{}`, signed by {}.",
+        bundle.name, bundle.signer
+    );
+    Ok(())
+}
+
+/// Print where the configuration comes from.
+///
+/// # Errors
+///
+/// This function will return an error if the configuration cannot be loaded.
+fn print_status(profile: &str) -> color_eyre::Result<()> {
+    let config = Config::load()?;
+    println!("Profile: {}", profile);
+    let source = match persist::data_dir() {
+        Some(_) => " (--data-dir)",
+        None => "",
+    };
+    match Config::path() {
+        Some(path) => println!("Config file: {}{}", path.display(), source),
+        None => println!("Config file: unavailable"),
+    }
+    match &config.remote {
+        Some(remote) => println!(
+            "Remote config: {} (signed by {}){}",
+            remote.url,
+            remote.bundle.signer,
+            if remote.offline {
+                ", offline: using the last fetched copy"
             } else {
-                // return
-                Err(eyre!(
-                    "API key found, but it is the wrong length. \
-                    Expected {} bytes, found {} bytes.",
-                    application::API_KEY_LEN,
-                    api_key.len()
-                ))
+                ""
             }
+        ),
+        None => println!("Remote config: none"),
+    }
+    if config.bundles.is_empty() {
+        println!("Signed bundles: none");
+    } else {
+        println!("Signed bundles:");
+        for bundle in &config.bundles {
+            println!("  {} (signed by {})", bundle.name, bundle.signer);
         }
-        Err(_) => {
-            // return
-            Err(eyre!(
-                "No API key found. Please run `monika login` to store an API key."
-            ))
+    }
+    match &config.policy {
+        Some(policy) => {
+            println!("Policy: {}", policy.source.display());
+            for key in policy.locked_keys() {
+                println!("  {} (locked)", key);
+            }
         }
+        None => println!("Policy: none"),
     }
+    let show = |dir: Option<PathBuf>| {
+        dir.map_or_else(
+            || "unavailable".to_string(),
+            |dir| dir.display().to_string(),
+        )
+    };
+    println!("State: {}{}", show(persist::state_dir()), source);
+    println!("  of this profile: {}", show(persist::profile_state_dir()));
+    println!("Cache: {}{}", show(persist::cache_dir()), source);
+    println!("  of this profile: {}", show(persist::profile_cache_dir()));
+    print_features(&config);
+    Ok(())
+}
+
+fn print_features(config: &Config) {
+    println!("Features:");
+    for feature in Feature::ALL {
+        println!("  {:<18} {}", feature.name(), feature.describe(config));
+    }
+}
+
+/// Check a config file strictly and print every problem found.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or has
+/// problems.
+fn validate_config(path: Option<&String>) -> color_eyre::Result<()> {
+    let path = match path {
+        Some(path) => path.into(),
+        None => Config::path().ok_or_else(|| eyre!("No config directory on this platform."))?,
+    };
+    let contents =
+        fs::read_to_string(&path).wrap_err_with(|| format!("Could not read {}", path.display()))?;
+
+    let problems = validate::check_config(&contents);
+    for problem in &problems {
+        eprintln!(
+            "{}",
+            problem.describe(&path.display().to_string(), &contents)
+        );
+    }
+    match problems.len() {
+        0 => {
+            println!("{} is valid.", path.display());
+            Ok(())
+        }
+        1 => Err(eyre!("Found 1 problem in {}.", path.display())),
+        n => Err(eyre!("Found {} problems in {}.", n, path.display())),
+    }
+}
+
+/// Report problems with the key bindings of the config, for the capabilities
+/// of this terminal, or of the most limited terminal outside of one.
+///
+/// # Errors
+///
+/// This function will return an error if the configuration cannot be loaded
+/// or any problem was found.
+fn lint_keys() -> color_eyre::Result<()> {
+    let config = Config::load()?;
+    let capabilities = terminal_capabilities(false).unwrap_or_default();
+    let problems = keymap::lint(&config, &capabilities);
+    for problem in &problems {
+        eprintln!("warning: {}", problem);
+    }
+    match problems.len() {
+        0 => {
+            println!("No key binding problems.");
+            Ok(())
+        }
+        1 => Err(eyre!("Found 1 key binding problem.")),
+        n => Err(eyre!("Found {} key binding problems.", n)),
+    }
+}
+
+/// Print the JSON Schema of `kind`, e.g. for editors to validate the config
+/// file against.
+///
+/// # Errors
+///
+/// This function will return an error if the schema cannot be serialized.
+fn print_schema(kind: &str) -> color_eyre::Result<()> {
+    let schema = match kind {
+        "config" => schemars::schema_for!(Config),
+        _ => unreachable!("clap only accepts known kinds"),
+    };
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+/// What the terminal supports, from the cache unless `probe` asks to detect
+/// it again.
+///
+/// # Errors
+///
+/// This function will return an error if not run in a terminal, or if the
+/// probe results cannot be cached.
+fn terminal_capabilities(probe: bool) -> color_eyre::Result<Capabilities> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(eyre!("This must be run in a terminal."));
+    }
+
+    // Answers to the probe must not be echoed or line buffered
+    terminal::enable_raw_mode()?;
+    let capabilities = if probe {
+        Capabilities::reprobe()
+    } else {
+        Ok(Capabilities::load_or_probe())
+    };
+    terminal::disable_raw_mode()?;
+    capabilities
+}
+
+/// Write a snapshot of the config and terminal to `path` for bug reports.
+///
+/// # Errors
+///
+/// This function will return an error if the configuration cannot be loaded,
+/// the terminal cannot be probed, or the file cannot be written.
+fn write_snapshot(path: &Path) -> color_eyre::Result<()> {
+    let config = Config::load()?;
+    let terminal = snapshot::Terminal {
+        key: capabilities::terminal_key(),
+        capabilities: terminal_capabilities(false)?,
+    };
+
+    let snapshot = Snapshot::capture(&config, terminal);
+    snapshot.write(path)?;
+    println!("Snapshot written to {}.", path.display());
+    for key in &snapshot.redacted {
+        println!("  {} was left out", key);
+    }
+    Ok(())
+}
+
+/// Print what the terminal supports, from the cache unless `probe` asks to
+/// detect it again.
+///
+/// # Errors
+///
+/// This function will return an error if not run in a terminal, or if the
+/// probe results cannot be cached.
+fn doctor(probe: bool) -> color_eyre::Result<()> {
+    let capabilities = terminal_capabilities(probe)?;
+
+    println!("Terminal: {}", capabilities::terminal_key());
+    for (name, available) in capabilities.list() {
+        println!("  {:<18} {}", name, if available { "yes" } else { "no" });
+    }
+    print_features(&Config::load_local()?);
+    Ok(())
 }
 
 fn main() -> color_eyre::Result<()> {
@@ -73,6 +661,21 @@ fn main() -> color_eyre::Result<()> {
         .propagate_version(true)
         .subcommand_required(false)
         .arg_required_else_help(true)
+        .arg(
+            arg!(--profile <NAME>)
+                .help("Profile whose credentials and settings to use.")
+                .default_value("default")
+                .global(true),
+        )
+        .arg(
+            arg!(--"data-dir" <DIR>)
+                .help(
+                    "Keep the config, state and caches in this directory instead of the \
+                    platform directories, e.g. for a portable install.",
+                )
+                .value_parser(value_parser!(PathBuf))
+                .global(true),
+        )
         .subcommand(
             Command::new("login")
                 .about("Parse an API to store in the keychain.")
@@ -82,18 +685,279 @@ fn main() -> color_eyre::Result<()> {
                             "API key to store in the keychain. \
                     This is the key used to authenticate with the API.",
                         )
-                        .required(true),
+                        .required_unless_present("paste-detect"),
+                )
+                .arg(
+                    arg!(--"paste-detect")
+                        .help(
+                            "Read the key from stdin instead, so pastes spanning \
+                    several lines work. End with Ctrl-D.",
+                        )
+                        .conflicts_with("API_KEY"),
+                ),
+        )
+        .subcommand(
+            Command::new("migrate-env")
+                .about(
+                    "Move an API key exported in the environment or shell startup files \
+                    into the keychain.",
+                )
+                .arg(arg!(-y --yes "Store the key without asking.")),
+        )
+        .subcommand(
+            Command::new("logout")
+                .about("Remove the API key of the profile from the keychain.")
+                .arg(arg!(--all "Remove the API keys of all profiles.")),
+        )
+        .subcommand(
+            Command::new("credentials")
+                .about("Manage the API keys stored in the keychain.")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("list").about("List stored API keys, masked, and their use."),
+                )
+                .subcommand(
+                    Command::new("prune")
+                        .about("Remove API keys that have not been used for a while.")
+                        .arg(
+                            arg!(--"unused-for" <AGE>)
+                                .help("Remove keys unused for this long, e.g. `90d`.")
+                                .required(true)
+                                .value_parser(timestamp::parse_duration),
+                        )
+                        .arg(arg!(--"dry-run" "Only list the keys that would be removed.")),
+                ),
+        )
+        .subcommand(
+            Command::new("run")
+                .about("Run the application.")
+                .arg(arg!(--goto <LINK>).help(
+                    "Open a specific view, e.g. `counter?pane=1`. \
+                    Links can be shared to point others at the same view.",
+                ))
+                .arg(
+                    arg!(--mirror <ADDR>)
+                        .help(
+                            "Serve a read-only web mirror of the TUI, \
+                            e.g. on 127.0.0.1:7878. Only loopback addresses are allowed.",
+                        )
+                        .value_parser(value_parser!(SocketAddr)),
+                )
+                .arg(arg!(--"from-snapshot" <FILE>).help(
+                    "Start with the settings and terminal capabilities of a snapshot \
+                    instead of the local config.",
+                ))
+                .arg(arg!(-q --quiet "Do not print a session summary on exit."))
+                .arg(
+                    arg!(--"safe-mode")
+                        .help(
+                            "Start with default settings and key bindings, run no \
+                            configured programs, and save nothing, to recover from a \
+                            broken config.",
+                        )
+                        .conflicts_with("from-snapshot"),
+                ),
+        )
+        .subcommand(
+            Command::new("exec")
+                .about("Run a command with the API key in its environment.")
+                .arg(
+                    arg!(<COMMAND> ...)
+                        .help(
+                            "Command to run, e.g. `monika exec -- ./deploy.sh`. \
+                    The key is passed as MONIKA_API_KEY.",
+                        )
+                        .trailing_var_arg(true)
+                        .allow_hyphen_values(true),
+                ),
+        )
+        .subcommand(
+            Command::new("profile")
+                .about("Manage configuration profiles.")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("import")
+                        .about("Import a signed profile bundle from your organization.")
+                        .arg(arg!(<FILE>).help("Path to the bundle file.")),
+                ),
+        )
+        .subcommand(
+            Command::new("show")
+                .about("Render a screen once to stdout and exit.")
+                .arg(arg!(<SCREEN>).help("Screen to render, as a deep link, e.g. `counter`."))
+                .arg(
+                    arg!(--width <COLUMNS>)
+                        .value_parser(value_parser!(u16).range(10..))
+                        .default_value("80"),
+                )
+                .arg(
+                    arg!(--height <ROWS>)
+                        .value_parser(value_parser!(u16).range(5..))
+                        .default_value("24"),
+                )
+                .arg(
+                    arg!(--color <WHEN>)
+                        .help("Emit ANSI colors.")
+                        .value_parser(["auto", "always", "never"])
+                        .default_value("auto"),
+                ),
+        )
+        .subcommand(
+            Command::new("export-dashboard")
+                .about("Export the dashboard as a standalone HTML or SVG file.")
+                .arg(arg!(--html <FILE>).help("Write an HTML page."))
+                .arg(arg!(--svg <FILE>).help("Write an SVG image."))
+                .group(
+                    ArgGroup::new("format")
+                        .args(["html", "svg"])
+                        .required(true)
+                        .multiple(true),
+                ),
+        )
+        .subcommand(Command::new("status").about("Show where configuration comes from."))
+        .subcommand(
+            Command::new("config")
+                .about("Work with the config file.")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("validate")
+                        .about("Check a config file, including for unknown keys.")
+                        .arg(arg!([FILE]).help("File to check instead of the user config.")),
+                ),
+        )
+        .subcommand(
+            Command::new("keys")
+                .about("Work with key bindings.")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("lint").about(
+                        "Check key bindings for conflicts and keys this terminal cannot send.",
+                    ),
+                ),
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Print the JSON Schema of a file format.")
+                .arg(
+                    arg!(<KIND>)
+                        .help("Format to describe.")
+                        .value_parser(["config"]),
+                ),
+        )
+        .subcommand(
+            Command::new("snapshot")
+                .about("Save config and terminal details, without secrets, for a bug report.")
+                .arg(
+                    arg!(--output <FILE>)
+                        .help("Where to write the snapshot.")
+                        .default_value("monika-snapshot.toml"),
+                ),
+        )
+        .subcommand(Command::new("whats-new").about("Show the release notes."))
+        .subcommand(Command::new("tutorial").about("Learn the basics of the UI step by step."))
+        .subcommand(
+            Command::new("doctor")
+                .about("Show which terminal features are available.")
+                .arg(
+                    arg!(--"probe-terminal")
+                        .help("Detect the terminal's features again instead of using the cache."),
                 ),
         )
-        .subcommand(Command::new("run").about("Run the application."))
         .get_matches();
 
+    let profile = matches
+        .get_one::<String>("profile")
+        .expect("profile has a default");
+    if let Some(dir) = matches.get_one::<PathBuf>("data-dir") {
+        persist::set_data_dir(path::absolute(dir)?);
+    }
+    persist::set_profile(profile);
+
     match matches.subcommand() {
-        Some(("login", sub_matches)) => register_api_key(
+        Some(("login", sub_matches)) => match sub_matches.get_one::<String>("API_KEY") {
+            Some(api_key) => register_api_key(api_key, profile),
+            None => read_pasted_key().and_then(|api_key| register_api_key(&api_key, profile)),
+        },
+        Some(("migrate-env", sub_matches)) => migrate_env(profile, sub_matches.get_flag("yes")),
+        Some(("logout", sub_matches)) => logout(profile, sub_matches.get_flag("all")),
+        Some(("credentials", sub_matches)) => match sub_matches.subcommand() {
+            Some(("list", _)) => list_credentials(),
+            Some(("prune", prune_matches)) => prune_credentials(
+                *prune_matches
+                    .get_one::<Duration>("unused-for")
+                    .expect("--unused-for is required"),
+                prune_matches.get_flag("dry-run"),
+            ),
+            _ => unreachable!("subcommand_required"),
+        },
+        Some(("exec", sub_matches)) => exec_with_api_key(
+            profile,
+            &sub_matches
+                .get_many::<String>("COMMAND")
+                .expect("COMMAND is required")
+                .collect::<Vec<_>>(),
+        ),
+        Some(("profile", sub_matches)) => match sub_matches.subcommand() {
+            Some(("import", import_matches)) => import_bundle(Path::new(
+                import_matches
+                    .get_one::<String>("FILE")
+                    .expect("FILE is required"),
+            )),
+            _ => unreachable!("subcommand_required"),
+        },
+        Some(("status", _)) => print_status(profile),
+        Some(("config", sub_matches)) => match sub_matches.subcommand() {
+            Some(("validate", validate_matches)) => {
+                validate_config(validate_matches.get_one::<String>("FILE"))
+            }
+            _ => unreachable!("subcommand_required"),
+        },
+        Some(("keys", sub_matches)) => match sub_matches.subcommand() {
+            Some(("lint", _)) => lint_keys(),
+            _ => unreachable!("subcommand_required"),
+        },
+        Some(("schema", sub_matches)) => print_schema(
             sub_matches
-                .get_one::<String>("API_KEY")
-                .expect("API_KEY is required"),
+                .get_one::<String>("KIND")
+                .expect("KIND is required"),
+        ),
+        Some(("snapshot", sub_matches)) => write_snapshot(Path::new(
+            sub_matches
+                .get_one::<String>("output")
+                .expect("has a default"),
+        )),
+        Some(("tutorial", _)) => run_tutorial(profile),
+        Some(("whats-new", _)) => {
+            println!("{}", whats_new::all());
+            Ok(())
+        }
+        Some(("doctor", sub_matches)) => doctor(sub_matches.get_flag("probe-terminal")),
+        Some(("export-dashboard", sub_matches)) => export_dashboard(
+            profile,
+            sub_matches.get_one::<String>("html"),
+            sub_matches.get_one::<String>("svg"),
+        ),
+        Some(("show", sub_matches)) => show_screen(
+            profile,
+            sub_matches
+                .get_one::<String>("SCREEN")
+                .expect("SCREEN is required"),
+            (
+                *sub_matches.get_one::<u16>("width").expect("has a default"),
+                *sub_matches.get_one::<u16>("height").expect("has a default"),
+            ),
+            sub_matches
+                .get_one::<String>("color")
+                .expect("has a default"),
+        ),
+        Some(("run", sub_matches)) => validate_config_and_run(
+            profile,
+            sub_matches.get_one::<String>("goto"),
+            sub_matches.get_one::<SocketAddr>("mirror"),
+            sub_matches.get_one::<String>("from-snapshot"),
+            sub_matches.get_flag("quiet"),
+            sub_matches.get_flag("safe-mode"),
         ),
-        _ => validate_config_and_run(),
+        _ => validate_config_and_run(profile, None, None, None, false, false),
     }
 }
@@ -0,0 +1,126 @@
+use std::{env, fs, path::PathBuf};
+
+use crate::credentials::{self, API_KEY_ENV};
+
+/// Shell startup files, relative to the home directory, that commonly
+/// export the API key.
+const RC_FILES: [&str; 7] = [
+    ".bashrc",
+    ".bash_profile",
+    ".profile",
+    ".zshrc",
+    ".zshenv",
+    ".zprofile",
+    ".config/fish/config.fish",
+];
+
+/// Where an API key was found outside the keychain.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Found {
+    /// The rc file and one-based line, or `None` for the environment.
+    pub location: Option<(PathBuf, usize)>,
+    pub line: String,
+    pub api_key: String,
+}
+
+impl Found {
+    /// The location and line, with the key masked, e.g.
+    /// `~/.zshrc:12: export MONIKA_API_KEY=sk_t…`.
+    pub fn describe(&self) -> String {
+        let line = self
+            .line
+            .replace(&self.api_key, &credentials::mask(&self.api_key));
+        match &self.location {
+            Some((path, number)) => format!("{}:{}: {}", path.display(), number, line),
+            None => format!("environment: {}", line),
+        }
+    }
+}
+
+/// The API key in the environment and the rc files that set it.
+pub fn find_api_keys() -> Vec<Found> {
+    let mut found = Vec::new();
+    if let Ok(api_key) = env::var(API_KEY_ENV) {
+        found.push(Found {
+            location: None,
+            line: format!("{}={}", API_KEY_ENV, api_key),
+            api_key,
+        });
+    }
+    let Some(home) = dirs::home_dir() else {
+        return found;
+    };
+    for name in RC_FILES {
+        let path = home.join(name);
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        for (number, line, api_key) in exports(&contents) {
+            found.push(Found {
+                location: Some((path.clone(), number)),
+                line: line.to_string(),
+                api_key,
+            });
+        }
+    }
+    found
+}
+
+/// Lines of `contents` that set the API key, with their one-based numbers
+/// and the key, for sh-like and fish syntax.
+fn exports(contents: &str) -> Vec<(usize, &str, String)> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let assignment = match words.as_slice() {
+                ["export", assignment, ..] | [assignment, ..] => {
+                    assignment.strip_prefix(API_KEY_ENV)?.strip_prefix('=')?
+                }
+                [] => return None,
+            };
+            Some((i + 1, line.trim(), unquote(assignment)?))
+        })
+        .chain(contents.lines().enumerate().filter_map(|(i, line)| {
+            // fish: set -x MONIKA_API_KEY value, with any mix of -g/-x/-U
+            let mut words = line.split_whitespace();
+            (words.next()? == "set").then_some(())?;
+            let mut words = words.skip_while(|word| word.starts_with('-'));
+            (words.next()? == API_KEY_ENV).then_some(())?;
+            Some((i + 1, line.trim(), unquote(words.next()?)?))
+        }))
+        .collect()
+}
+
+fn unquote(value: &str) -> Option<String> {
+    let value = value.trim_matches(|c| c == '"' || c == '\'');
+    (!value.is_empty() && !value.starts_with('$')).then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_exported_keys() {
+        let rc = "alias ll='ls -l'\n\
+                  export MONIKA_API_KEY=\"abc123\"\n\
+                  MONIKA_API_KEY=def456\n\
+                  export MONIKA_API_KEY=$(pass show monika)\n\
+                  set -gx MONIKA_API_KEY 'ghi789'\n\
+                  # MONIKA_API_KEY=commented\n";
+        let keys: Vec<(usize, String)> = exports(rc)
+            .into_iter()
+            .map(|(number, _, api_key)| (number, api_key))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (2, "abc123".to_string()),
+                (3, "def456".to_string()),
+                (5, "ghi789".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use color_eyre::eyre::{eyre, WrapErr};
+
+use crate::{render, tasks};
+
+/// How long an idle event stream waits before sending a keep-alive comment,
+/// which is also how it notices a browser that went away.
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// How long a browser may take to send its request, or to take a write,
+/// before it is dropped. Stalled clients would otherwise hold a thread
+/// forever.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The latest frame, versioned so event streams know when to send it.
+#[derive(Default)]
+struct Frame {
+    version: u64,
+    html: String,
+}
+
+/// A read-only web page mirroring the TUI, served on a loopback address.
+///
+/// The loop publishes each rendered frame as HTML; browsers load the page
+/// once and receive new frames over server-sent events.
+#[derive(Clone)]
+pub struct Mirror {
+    frame: Arc<(Mutex<Frame>, Condvar)>,
+    addr: SocketAddr,
+}
+
+impl Mirror {
+    /// Start serving on `addr` in the background.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `addr` is not a loopback address
+    /// or cannot be bound.
+    pub fn serve(addr: SocketAddr) -> color_eyre::Result<Self> {
+        if !addr.ip().is_loopback() {
+            return Err(eyre!(
+                "The web mirror only serves on localhost, but {} is not a loopback address.",
+                addr
+            ));
+        }
+        let listener =
+            TcpListener::bind(addr).wrap_err_with(|| format!("Could not listen on {}", addr))?;
+
+        let mirror = Self {
+            frame: Arc::default(),
+            addr: listener.local_addr()?,
+        };
+        let server = mirror.clone();
+        tasks::spawn_service("web mirror", move || {
+            for stream in listener.incoming().flatten() {
+                let mirror = server.clone();
+                tasks::spawn_service("web mirror client", move || mirror.handle(stream));
+            }
+        });
+        Ok(mirror)
+    }
+
+    /// The address actually served on, e.g. with the port chosen for port 0.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Publish a frame, rendered as an HTML fragment, to connected browsers.
+    pub fn publish(&self, html: String) {
+        let (lock, changed) = &*self.frame;
+        let mut frame = lock.lock().expect("mirror lock poisoned");
+        if frame.html != html {
+            frame.html = html;
+            frame.version += 1;
+            changed.notify_all();
+        }
+    }
+
+    fn handle(&self, stream: TcpStream) {
+        let timeouts = stream
+            .set_read_timeout(Some(CLIENT_TIMEOUT))
+            .and_then(|()| stream.set_write_timeout(Some(CLIENT_TIMEOUT)));
+        if timeouts.is_err() {
+            return;
+        }
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).is_err() {
+            return;
+        }
+        let mut host = None;
+        let mut header = String::new();
+        while reader.read_line(&mut header).is_ok_and(|n| n > 2) {
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("host") {
+                    host = Some(value.trim().to_string());
+                }
+            }
+            header.clear();
+        }
+        // A page on another site can point its own name at 127.0.0.1 (DNS
+        // rebinding) and read the mirror, but not make the browser send our
+        // host name
+        if !host.is_some_and(|host| is_own_host(&host, self.addr.port())) {
+            let _ = write!(
+                &stream,
+                "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+            return;
+        }
+
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+        let _ = match path {
+            "/" => self.send_page(&stream),
+            "/events" => self.send_events(&stream),
+            _ => write!(
+                &stream,
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            ),
+        };
+    }
+
+    fn send_page(&self, mut stream: &TcpStream) -> std::io::Result<()> {
+        let html = self
+            .frame
+            .0
+            .lock()
+            .expect("mirror lock poisoned")
+            .html
+            .clone();
+        let page = render::html_page(&format!(
+            "<pre id=\"screen\" style=\"font-family:monospace\">{}</pre>\n<script>\n\
+            new EventSource('/events').onmessage = (event) => {{\n  \
+            document.getElementById('screen').innerHTML = event.data;\n}};\n</script>",
+            html
+        ));
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\
+            Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            page.len(),
+            page
+        )
+    }
+
+    fn send_events(&self, mut stream: &TcpStream) -> std::io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n"
+        )?;
+
+        let (lock, changed) = &*self.frame;
+        let mut seen = 0;
+        loop {
+            let frame = lock.lock().expect("mirror lock poisoned");
+            let (frame, _) = changed
+                .wait_timeout_while(frame, KEEP_ALIVE, |frame| frame.version == seen)
+                .expect("mirror lock poisoned");
+            if frame.version == seen {
+                drop(frame);
+                stream.write_all(b": keep-alive\n\n")?;
+                continue;
+            }
+            seen = frame.version;
+            // Multi-line data is sent as one `data:` line per line of HTML
+            let mut event = String::new();
+            for line in frame.html.lines() {
+                event.push_str("data: ");
+                event.push_str(line);
+                event.push('\n');
+            }
+            drop(frame);
+            event.push('\n');
+            stream.write_all(event.as_bytes())?;
+        }
+    }
+}
+
+/// Whether `host` names the mirror itself, i.e. loopback on `port`.
+fn is_own_host(host: &str, port: u16) -> bool {
+    ["127.0.0.1", "localhost", "[::1]"]
+        .iter()
+        .any(|name| host == format!("{}:{}", name, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_serves_its_own_host() {
+        assert!(is_own_host("127.0.0.1:7878", 7878));
+        assert!(is_own_host("localhost:7878", 7878));
+        assert!(is_own_host("[::1]:7878", 7878));
+        assert!(!is_own_host("localhost:8080", 7878));
+        assert!(!is_own_host("attacker.example:7878", 7878));
+        assert!(!is_own_host("127.0.0.1", 7878));
+    }
+
+    #[test]
+    fn serves_on_the_port_it_was_given() {
+        use std::io::Read;
+
+        let mirror = Mirror::serve("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = mirror.addr();
+        assert_ne!(addr.port(), 0);
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET / HTTP/1.1\r\nHost: {}\r\n\r\n", addr).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+    }
+}
@@ -0,0 +1,161 @@
+use std::{collections::VecDeque, time::Instant};
+
+use crate::{theme::Role, toast::Toast};
+
+/// How many notifications the center keeps; older ones are dropped.
+pub const NOTIFICATIONS_MAX: usize = 100;
+
+/// A toast kept after it disappears from the status line.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub role: Role,
+    pub text: String,
+    pub at: Instant,
+    /// The quick command to run again, if this reports a failed run.
+    pub retry: Option<String>,
+}
+
+/// Which notifications the center lists.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum Filter {
+    #[default]
+    All,
+    Warnings,
+    Errors,
+}
+
+impl Filter {
+    pub fn next(self) -> Self {
+        match self {
+            Filter::All => Filter::Warnings,
+            Filter::Warnings => Filter::Errors,
+            Filter::Errors => Filter::All,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Filter::All => "all",
+            Filter::Warnings => "warnings and errors",
+            Filter::Errors => "errors",
+        }
+    }
+
+    fn matches(self, role: Role) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Warnings => matches!(role, Role::Warning | Role::Error),
+            Filter::Errors => role == Role::Error,
+        }
+    }
+}
+
+/// The session's notification history and the state of the screen listing
+/// it, newest first.
+#[derive(Debug, Default, Clone)]
+pub struct NotificationCenter {
+    items: VecDeque<Notification>,
+    /// Notifications that arrived since the center was last opened.
+    pub unread: usize,
+    pub filter: Filter,
+    /// Index into the filtered list.
+    pub selected: usize,
+    pub open: bool,
+    /// Show the selected notification in full.
+    pub details: bool,
+}
+
+impl NotificationCenter {
+    pub fn push(&mut self, toast: &Toast, retry: Option<String>) {
+        self.items.push_front(Notification {
+            role: toast.role,
+            text: toast.text.clone(),
+            at: toast.shown_at,
+            retry,
+        });
+        self.items.truncate(NOTIFICATIONS_MAX);
+        if self.open {
+            // Keep the same notification selected as the list shifts down
+            if self.filter.matches(toast.role) && !self.visible().is_empty() {
+                self.selected = (self.selected + 1).min(self.visible().len() - 1);
+            }
+        } else {
+            self.unread = (self.unread + 1).min(NOTIFICATIONS_MAX);
+        }
+    }
+
+    /// The notifications passing the filter, newest first.
+    pub fn visible(&self) -> Vec<&Notification> {
+        self.items
+            .iter()
+            .filter(|notification| self.filter.matches(notification.role))
+            .collect()
+    }
+
+    pub fn selected(&self) -> Option<&Notification> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.details = false;
+        self.selected = 0;
+        self.unread = 0;
+    }
+
+    /// Move the selection by `delta`, staying within the list.
+    pub fn select(&mut self, delta: isize) {
+        let last = self.visible().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    pub fn cycle_filter(&mut self) {
+        self.filter = self.filter.next();
+        self.selected = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toast(role: Role, text: &str) -> Toast {
+        Toast::new(role, text, Instant::now())
+    }
+
+    #[test]
+    fn filters_by_severity() {
+        let mut center = NotificationCenter::default();
+        center.push(&toast(Role::Success, "copied"), None);
+        center.push(&toast(Role::Warning, "counter high"), None);
+        center.push(&toast(Role::Error, "`ls` exited"), Some("ls".to_string()));
+        assert_eq!(center.unread, 3);
+
+        center.toggle();
+        assert_eq!(center.unread, 0);
+        assert_eq!(center.visible().len(), 3);
+        center.cycle_filter();
+        assert_eq!(center.visible().len(), 2);
+        center.cycle_filter();
+        let errors = center.visible();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].retry.as_deref(), Some("ls"));
+    }
+
+    #[test]
+    fn keeps_the_newest() {
+        let mut center = NotificationCenter::default();
+        for i in 0..NOTIFICATIONS_MAX + 5 {
+            center.push(&toast(Role::Info, &i.to_string()), None);
+        }
+        let visible = center.visible();
+        assert_eq!(visible.len(), NOTIFICATIONS_MAX);
+        assert_eq!(visible[0].text, (NOTIFICATIONS_MAX + 4).to_string());
+
+        center.toggle();
+        center.select(-3);
+        assert_eq!(center.selected, 0);
+        center.select(1000);
+        assert_eq!(center.selected, NOTIFICATIONS_MAX - 1);
+    }
+}
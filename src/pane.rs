@@ -0,0 +1,248 @@
+use std::{
+    collections::BTreeMap,
+    ops::{Index, IndexMut},
+    time::{Duration, Instant},
+};
+
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use serde::{Deserialize, Serialize};
+
+/// Identifies a region of the main screen.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaneId {
+    /// The counter, refreshed every second.
+    Live,
+    /// The counter, refreshed on `r`.
+    Manual,
+    /// Quick command output, shown once there is some.
+    Output,
+    /// The status bar.
+    Status,
+}
+
+impl PaneId {
+    /// Panes that show the counter, in focus order.
+    pub const COUNTERS: [Self; 2] = [Self::Live, Self::Manual];
+
+    /// Position among the counter panes, as used in deep links.
+    pub fn index(self) -> Option<usize> {
+        Self::COUNTERS.iter().position(|id| *id == self)
+    }
+
+    /// The pane focused after this one. The output pane takes focus after
+    /// the counters while `with_output` is set, i.e. while it has content.
+    pub fn next(self, with_output: bool) -> Self {
+        match self.index().map(|index| index + 1) {
+            Some(index) if index < Self::COUNTERS.len() => Self::COUNTERS[index],
+            Some(_) if with_output => Self::Output,
+            _ => Self::COUNTERS[0],
+        }
+    }
+
+    /// A focusable pane by position (`1`) or name (`manual`).
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.parse::<usize>() {
+            Ok(index) => Self::COUNTERS.get(index).copied(),
+            Err(_) => Self::COUNTERS.into_iter().find(|id| id.name() == text),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Live => "live",
+            Self::Manual => "manual",
+            Self::Output => "output",
+            Self::Status => "status",
+        }
+    }
+}
+
+/// How often a data-bound pane pulls fresh data.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Refresh {
+    /// Refresh automatically once the interval has elapsed.
+    Every(Duration),
+    /// Only refresh when the user asks for it.
+    Manual,
+}
+
+/// A pane showing a snapshot of the counter, taken at its last refresh.
+#[derive(Debug, Copy, Clone)]
+pub struct Pane {
+    pub refresh: Refresh,
+    pub last_refreshed: Instant,
+    pub counter: i32,
+}
+
+impl Pane {
+    pub fn new(refresh: Refresh, now: Instant) -> Self {
+        Self {
+            refresh,
+            last_refreshed: now,
+            counter: 0,
+        }
+    }
+
+    /// Whether the scheduler should refresh this pane at `now`.
+    pub fn is_due(&self, now: Instant) -> bool {
+        match self.refresh {
+            Refresh::Every(interval) => now.duration_since(self.last_refreshed) >= interval,
+            Refresh::Manual => false,
+        }
+    }
+
+    pub fn refreshed(self, counter: i32, now: Instant) -> Self {
+        Self {
+            counter,
+            last_refreshed: now,
+            ..self
+        }
+    }
+}
+
+/// The counter panes, by id. Only focusable panes have one.
+#[derive(Debug, Copy, Clone)]
+pub struct Panes([Pane; 2]);
+
+impl Panes {
+    pub fn new(now: Instant) -> Self {
+        Self([
+            Pane::new(Refresh::Every(Duration::from_secs(1)), now),
+            Pane::new(Refresh::Manual, now),
+        ])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (PaneId, &Pane)> {
+        PaneId::COUNTERS.into_iter().zip(&self.0)
+    }
+
+    pub fn map(self, f: impl FnMut(Pane) -> Pane) -> Self {
+        Self(self.0.map(f))
+    }
+}
+
+impl Index<PaneId> for Panes {
+    type Output = Pane;
+
+    /// # Panics
+    ///
+    /// Panics if `id` is not a counter pane.
+    fn index(&self, id: PaneId) -> &Pane {
+        &self.0[id.index().expect("only counter panes have state")]
+    }
+}
+
+impl IndexMut<PaneId> for Panes {
+    fn index_mut(&mut self, id: PaneId) -> &mut Pane {
+        &mut self.0[id.index().expect("only counter panes have state")]
+    }
+}
+
+/// A rectangle of the screen, in cells.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub struct Area {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl From<Rect> for Area {
+    fn from(rect: Rect) -> Self {
+        Self {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+        }
+    }
+}
+
+impl From<Area> for Rect {
+    fn from(area: Area) -> Self {
+        Rect::new(area.x, area.y, area.width, area.height)
+    }
+}
+
+/// Where each pane of the main screen goes.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ScreenLayout {
+    pub screen: Area,
+    pub areas: BTreeMap<PaneId, Area>,
+}
+
+impl ScreenLayout {
+    /// Split `screen` into the counter panes, the output pane if
+    /// `with_output`, and the status bar.
+    pub fn compute(screen: Rect, with_output: bool) -> Self {
+        let mut panes = vec![
+            (PaneId::Live, Constraint::Fill(1)),
+            (PaneId::Manual, Constraint::Fill(1)),
+        ];
+        if with_output {
+            panes.push((PaneId::Output, Constraint::Fill(2)));
+        }
+        panes.push((PaneId::Status, Constraint::Length(1)));
+
+        let rects = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints(panes.iter().map(|(_, constraint)| *constraint))
+            .split(screen);
+        Self {
+            screen: Area::from(screen),
+            areas: panes
+                .iter()
+                .zip(rects.iter())
+                .map(|((id, _), rect)| (*id, Area::from(*rect)))
+                .collect(),
+        }
+    }
+
+    /// The area of `id`, empty if it is not shown.
+    pub fn area(&self, id: PaneId) -> Rect {
+        self.areas.get(&id).copied().unwrap_or_default().into()
+    }
+
+    /// The pane at `column`, `row`, if any.
+    pub fn pane_at(&self, column: u16, row: u16) -> Option<PaneId> {
+        let position = ratatui::layout::Position::new(column, row);
+        self.areas
+            .iter()
+            .find(|(_, area)| Rect::from(**area).contains(position))
+            .map(|(id, _)| *id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lays_out_panes_by_id() {
+        let layout = ScreenLayout::compute(Rect::new(0, 0, 40, 22), true);
+        assert_eq!(layout.area(PaneId::Status), Rect::new(1, 20, 38, 1));
+        assert_eq!(layout.area(PaneId::Output).height, 9);
+        assert_eq!(layout.pane_at(5, 20), Some(PaneId::Status));
+        assert_eq!(layout.pane_at(0, 0), None);
+
+        let without_output = ScreenLayout::compute(Rect::new(0, 0, 40, 22), false);
+        assert_eq!(without_output.area(PaneId::Output), Rect::default());
+
+        let json = serde_json::to_string(&layout).unwrap();
+        assert!(json.contains("\"output\":{\"x\":1"));
+        assert_eq!(serde_json::from_str::<ScreenLayout>(&json).unwrap(), layout);
+    }
+
+    #[test]
+    fn parses_pane_ids() {
+        assert_eq!(PaneId::parse("1"), Some(PaneId::Manual));
+        assert_eq!(PaneId::parse("live"), Some(PaneId::Live));
+        assert_eq!(PaneId::parse("status"), None);
+        assert_eq!(PaneId::parse("2"), None);
+        assert_eq!(PaneId::Manual.next(false), PaneId::Live);
+        assert_eq!(PaneId::Manual.next(true), PaneId::Output);
+        assert_eq!(PaneId::Output.next(true), PaneId::Live);
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
+
+/// Set in safe mode, where nothing the app remembers is written.
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--data-dir` to keep everything in one place.
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set by `--profile`, whose data is kept apart from other profiles'.
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Keep the config, state and caches under `dir` instead of the platform
+/// directories, e.g. for a portable install on a USB stick.
+pub fn set_data_dir(dir: PathBuf) {
+    let _ = DATA_DIR.set(dir);
+}
+
+/// The `--data-dir` override, if given.
+pub fn data_dir() -> Option<&'static Path> {
+    DATA_DIR.get().map(PathBuf::as_path)
+}
+
+/// Keep profile data in the directories of `profile`.
+pub fn set_profile(profile: &str) {
+    let _ = PROFILE.set(profile.to_string());
+}
+
+/// Where the config file and imported bundles are kept, e.g.
+/// `~/.config/monika`.
+pub fn config_dir() -> Option<PathBuf> {
+    match data_dir() {
+        Some(dir) => Some(dir.join("config")),
+        None => dirs::config_dir().map(|dir| dir.join("monika")),
+    }
+}
+
+/// Where state and preferences are kept, e.g. `~/.local/state/monika`.
+pub fn state_dir() -> Option<PathBuf> {
+    match data_dir() {
+        Some(dir) => Some(dir.join("state")),
+        None => dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|dir| dir.join("monika")),
+    }
+}
+
+/// Where caches are kept, e.g. `~/.cache/monika`.
+pub fn cache_dir() -> Option<PathBuf> {
+    match data_dir() {
+        Some(dir) => Some(dir.join("cache")),
+        None => dirs::cache_dir().map(|dir| dir.join("monika")),
+    }
+}
+
+/// Where state of the selected profile is kept, e.g.
+/// `~/.local/state/monika/work`.
+pub fn profile_state_dir() -> Option<PathBuf> {
+    state_dir().map(|dir| profile_dir(&dir, selected_profile()))
+}
+
+/// Where caches of the selected profile are kept, e.g. `~/.cache/monika/work`.
+pub fn profile_cache_dir() -> Option<PathBuf> {
+    cache_dir().map(|dir| profile_dir(&dir, selected_profile()))
+}
+
+fn selected_profile() -> &'static str {
+    PROFILE.get().map_or("default", String::as_str)
+}
+
+/// The directory of `profile` in `dir`. Profile names are free-form, so
+/// anything but letters, digits, `-`, `_` and inner dots is percent-encoded
+/// to keep the name a single, ordinary path component. An empty name becomes
+/// `%`, which no other name encodes to.
+fn profile_dir(dir: &Path, profile: &str) -> PathBuf {
+    if profile.is_empty() {
+        return dir.join("%");
+    }
+    let mut name = String::new();
+    for (i, byte) in profile.bytes().enumerate() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => name.push(byte.into()),
+            b'.' if i > 0 => name.push('.'),
+            byte => name.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    dir.join(name)
+}
+
+/// Stop writing state, caches and preferences for the rest of the process.
+pub fn set_read_only() {
+    READ_ONLY.store(true, Ordering::Relaxed);
+}
+
+/// Atomically write state, a cache or a preference to `path`, creating its
+/// directory first. Does nothing once persistence is read-only.
+///
+/// # Errors
+///
+/// This function will return an error if the directory or file cannot be
+/// written.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    if READ_ONLY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    atomic_write(path, contents)
+}
+
+/// Replace `path` with `contents` so that a crash or power loss leaves
+/// either the old or the new file, never a truncated one: the contents go
+/// to a temporary file next to it, are flushed to disk, and the temporary
+/// file is renamed over `path`.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be written or
+/// replaced. The temporary file is removed in that case.
+pub fn atomic_write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let temp = temp_path(path);
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    });
+    let replaced = written.and_then(|()| replace(&temp, path));
+    if replaced.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    replaced?;
+    sync_dir(path);
+    Ok(())
+}
+
+/// A temporary file in the same directory as `path`, so the rename never
+/// crosses file systems, e.g. `.config.toml.1234.tmp`.
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map_or_else(|| "file".into(), |name| name.to_string_lossy().into_owned());
+    path.with_file_name(format!(".{}.{}.tmp", name, process::id()))
+}
+
+fn replace(temp: &Path, path: &Path) -> io::Result<()> {
+    match fs::rename(temp, path) {
+        // Windows refuses to replace a file another process holds open
+        // without sharing deletes, e.g. a virus scanner. Removing it first
+        // loses atomicity, but beats failing to save at all.
+        Err(err) if cfg!(windows) && err.kind() == io::ErrorKind::PermissionDenied => {
+            fs::remove_file(path)?;
+            fs::rename(temp, path)
+        }
+        result => result,
+    }
+}
+
+/// Flush the rename itself to disk. Only possible, and needed, on Unix.
+fn sync_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        let _ = File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_get_a_directory_each() {
+        let dir = Path::new("state");
+        assert_eq!(profile_dir(dir, "work"), dir.join("work"));
+        assert_eq!(profile_dir(dir, "ci.eu-1"), dir.join("ci.eu-1"));
+        // Names cannot leave the directory or hide in it
+        assert_eq!(profile_dir(dir, ".."), dir.join("%2E."));
+        assert_eq!(profile_dir(dir, "../etc"), dir.join("%2E.%2Fetc"));
+        assert_eq!(profile_dir(dir, "my work"), dir.join("my%20work"));
+        assert_eq!(profile_dir(dir, ""), dir.join("%"));
+    }
+
+    #[test]
+    fn replaces_files_without_leftovers() {
+        let dir = std::env::temp_dir().join(format!("monika-persist-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.toml");
+
+        atomic_write(&path, "version = 1\n").unwrap();
+        atomic_write(&path, "version = 2\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "version = 2\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // A failed write leaves the old contents and no temporary file
+        assert!(atomic_write(&dir.join("missing").join("state.toml"), "x").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
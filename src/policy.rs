@@ -0,0 +1,82 @@
+use std::{fs, io, path::PathBuf};
+
+use color_eyre::eyre::WrapErr;
+use serde::Deserialize;
+
+/// Organization policy read from a system-wide, admin-owned file:
+///
+/// ```toml
+/// [locked]
+/// theme = "deuteranopia"
+/// quick_commands = []
+/// ```
+///
+/// Settings under `[locked]` take precedence over every other config layer
+/// and cannot be changed by the user.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Policy {
+    #[serde(default)]
+    pub locked: toml::Table,
+    #[serde(skip)]
+    pub source: PathBuf,
+}
+
+impl Policy {
+    /// System location of the policy file.
+    pub fn path() -> PathBuf {
+        if cfg!(windows) {
+            let program_data =
+                std::env::var_os("ProgramData").unwrap_or_else(|| r"C:\ProgramData".into());
+            PathBuf::from(program_data)
+                .join("monika")
+                .join("policy.toml")
+        } else if cfg!(target_os = "macos") {
+            PathBuf::from("/Library/Application Support/monika/policy.toml")
+        } else {
+            PathBuf::from("/etc/monika/policy.toml")
+        }
+    }
+
+    /// Load the policy file, if the system has one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file exists but cannot be
+    /// read or parsed. A broken policy is not silently ignored.
+    pub fn load() -> color_eyre::Result<Option<Self>> {
+        let path = Self::path();
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                let mut policy: Self = toml::from_str(&contents)
+                    .wrap_err_with(|| format!("Invalid policy file {}", path.display()))?;
+                policy.source = path;
+                Ok(Some(policy))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => {
+                Err(err).wrap_err_with(|| format!("Could not read policy file {}", path.display()))
+            }
+        }
+    }
+
+    /// Dotted paths of every locked setting, e.g. `profiles.work.netrc_machine`.
+    pub fn locked_keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        collect_keys(&self.locked, "", &mut keys);
+        keys
+    }
+}
+
+fn collect_keys(table: &toml::Table, prefix: &str, keys: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::Table(table) => collect_keys(table, &path, keys),
+            _ => keys.push(path),
+        }
+    }
+}
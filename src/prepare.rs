@@ -0,0 +1,90 @@
+use std::{
+    ops::Range,
+    sync::mpsc::{self, Sender},
+};
+
+use crate::{
+    highlight::{self, Token},
+    tasks,
+};
+
+/// The output pane's content analyzed for drawing, so `view` only has to
+/// look things up.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PreparedOutput {
+    /// The output version this was prepared from.
+    pub version: u64,
+    /// Whether the output as a whole is a JSON document.
+    pub is_json: bool,
+    /// Syntax tokens of each line, if it is JSON.
+    pub tokens: Vec<Vec<(Range<usize>, Token)>>,
+}
+
+impl PreparedOutput {
+    fn new(version: u64, lines: &[String]) -> Self {
+        let is_json = serde_json::from_str::<serde_json::Value>(&lines.join("\n")).is_ok();
+        let tokens = if is_json {
+            lines
+                .iter()
+                .map(|line| highlight::json_tokens(line))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Self {
+            version,
+            is_json,
+            tokens,
+        }
+    }
+}
+
+/// A background thread preparing output for drawing, keeping the work off
+/// the render path.
+pub struct Preparer {
+    jobs: Sender<(u64, Vec<String>)>,
+}
+
+impl Preparer {
+    /// Start the thread, which passes each prepared output to `emit`.
+    pub fn spawn<F>(emit: F) -> Self
+    where
+        F: Fn(PreparedOutput) + Send + 'static,
+    {
+        let (jobs, queue) = mpsc::channel::<(u64, Vec<String>)>();
+        tasks::spawn("output preparer", move || {
+            while let Ok(job) = queue.recv() {
+                // Only the newest output matters if several piled up
+                let (version, lines) = queue.try_iter().last().unwrap_or(job);
+                emit(PreparedOutput::new(version, &lines));
+            }
+        });
+        Self { jobs }
+    }
+
+    /// Prepare `lines`, the output at `version`, in the background.
+    pub fn submit(&self, version: u64, lines: Vec<String>) {
+        // The thread only stops when this is dropped
+        let _ = self.jobs.send((version, lines));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prepares_newest_output() {
+        let (tx, rx) = mpsc::channel();
+        let preparer = Preparer::spawn(move |prepared| tx.send(prepared).unwrap());
+        preparer.submit(1, vec!["{\"a\":".to_string(), "1}".to_string()]);
+
+        let prepared = rx.recv().unwrap();
+        assert_eq!(prepared.version, 1);
+        assert!(prepared.is_json);
+        assert_eq!(prepared.tokens[1], vec![(0..1, Token::Literal)]);
+
+        preparer.submit(2, vec!["plain text".to_string()]);
+        assert!(!rx.recv().unwrap().is_json);
+    }
+}
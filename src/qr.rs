@@ -0,0 +1,102 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Paragraph, Widget, Wrap},
+};
+
+/// Light modules around the code; scanners need some margin to find it.
+const QUIET_ZONE: usize = 2;
+
+/// A QR code of `text`, drawn with half blocks so each cell holds two
+/// modules stacked vertically.
+///
+/// When the area is too small for the code, `text` is shown as plain text
+/// instead so it can still be typed in.
+pub struct QrCode<'a> {
+    text: &'a str,
+    modules: Option<(usize, Vec<bool>)>,
+}
+
+impl<'a> QrCode<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let modules = qrcode::QrCode::new(text).ok().map(|code| {
+            let dark = code
+                .to_colors()
+                .into_iter()
+                .map(|color| color == qrcode::Color::Dark)
+                .collect();
+            (code.width(), dark)
+        });
+        Self { text, modules }
+    }
+
+    /// Columns and rows the code needs, including the quiet zone.
+    pub fn size(&self) -> Option<(u16, u16)> {
+        let (width, _) = self.modules.as_ref()?;
+        let side = width + 2 * QUIET_ZONE;
+        Some((
+            u16::try_from(side).ok()?,
+            u16::try_from(side.div_ceil(2)).ok()?,
+        ))
+    }
+}
+
+impl Widget for QrCode<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let fits = self
+            .size()
+            .is_some_and(|(cols, rows)| cols <= area.width && rows <= area.height);
+        let Some((width, modules)) = self.modules.as_ref().filter(|_| fits) else {
+            Paragraph::new(self.text)
+                .wrap(Wrap { trim: false })
+                .render(area, buf);
+            return;
+        };
+
+        // Outside the code (the quiet zone) counts as light
+        let dark = |x: usize, y: usize| {
+            let (x, y) = (x.checked_sub(QUIET_ZONE), y.checked_sub(QUIET_ZONE));
+            x.zip(y)
+                .is_some_and(|(x, y)| x < *width && y < *width && modules[y * width + x])
+        };
+        let color = |dark: bool| if dark { Color::Black } else { Color::White };
+
+        let side = width + 2 * QUIET_ZONE;
+        for row in 0..side.div_ceil(2) {
+            for col in 0..side {
+                let (top, bottom) = (dark(col, 2 * row), dark(col, 2 * row + 1));
+                buf[(area.x + col as u16, area.y + row as u16)]
+                    .set_symbol("▀")
+                    .set_style(Style::new().fg(color(top)).bg(color(bottom)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_code_when_it_fits() {
+        let qr = QrCode::new("counter?pane=1");
+        // Version 1 codes are 21 modules wide
+        assert_eq!(qr.size(), Some((25, 13)));
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 25, 13));
+        qr.render(buf.area, &mut buf);
+        // The quiet zone fills the first row, then the finder pattern starts
+        let quiet = &buf[(2, 0)];
+        assert_eq!((quiet.fg, quiet.bg), (Color::White, Color::White));
+        let corner = &buf[(2, 1)];
+        assert_eq!((corner.fg, corner.bg), (Color::Black, Color::Black));
+    }
+
+    #[test]
+    fn falls_back_to_text() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 5));
+        QrCode::new("counter?pane=1").render(buf.area, &mut buf);
+        assert_eq!(buf[(0, 0)].symbol(), "c");
+    }
+}
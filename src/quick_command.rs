@@ -0,0 +1,152 @@
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    process::{Command, Stdio},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::tasks::{self, CancelToken};
+
+/// How often a running command checks whether it was cancelled.
+const CANCEL_POLL: Duration = Duration::from_millis(50);
+
+/// A command the user explicitly allowed in the config, e.g.
+///
+/// ```toml
+/// [[quick_commands]]
+/// name = "ping api"
+/// program = "curl"
+/// args = ["-sS", "https://example.com/health"]
+/// key = "p"
+/// ```
+///
+/// Only commands listed like this can be launched from the TUI. They are run
+/// directly, never through a shell, so arguments are not re-interpreted.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct QuickCommand {
+    pub name: String,
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Key that runs it besides its number key, e.g. `p` or `ctrl-p`.
+    pub key: Option<String>,
+}
+
+/// Progress of a running quick command.
+#[derive(Debug)]
+pub enum CommandEvent {
+    /// A line of stdout or stderr.
+    Line(String),
+    /// The process exited; `None` if it was killed by a signal.
+    Exited(Option<i32>),
+}
+
+/// Start `command` and stream its output to `emit` from background threads.
+/// Once `cancel` is cancelled the process is killed and nothing more is
+/// emitted.
+///
+/// # Errors
+///
+/// This function will return an error if the process could not be started.
+/// Failures after that are reported through `emit` instead.
+pub fn spawn<F>(command: &QuickCommand, cancel: CancelToken, emit: F) -> io::Result<()>
+where
+    F: Fn(CommandEvent) + Send + Sync + 'static,
+{
+    let mut child = Command::new(&command.program)
+        .args(&command.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let emit = Arc::new(emit);
+    let name = &command.name;
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|out| forward_lines(format!("`{}` stdout", name), out, &cancel, emit.clone())),
+        child
+            .stderr
+            .take()
+            .map(|err| forward_lines(format!("`{}` stderr", name), err, &cancel, emit.clone())),
+    ];
+
+    tasks::spawn(format!("`{}`", name), move || {
+        // Poll rather than block in `wait`, so a cancellation can kill it
+        let status = loop {
+            if cancel.is_cancelled() {
+                let _ = child.kill();
+            }
+            match child.try_wait() {
+                Ok(Some(status)) => break status.code(),
+                Ok(None) => thread::sleep(CANCEL_POLL),
+                Err(_) => break None,
+            }
+        };
+        for reader in readers.into_iter().flatten() {
+            let _ = reader.join();
+        }
+        if !cancel.is_cancelled() {
+            emit(CommandEvent::Exited(status));
+        }
+    });
+    Ok(())
+}
+
+fn forward_lines<R, F>(
+    name: String,
+    stream: R,
+    cancel: &CancelToken,
+    emit: Arc<F>,
+) -> thread::JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    F: Fn(CommandEvent) + Send + Sync + 'static,
+{
+    let cancel = cancel.clone();
+    tasks::spawn(name, move || {
+        for line in BufReader::new(stream).lines() {
+            match line {
+                Ok(line) if !cancel.is_cancelled() => emit(CommandEvent::Line(line)),
+                _ => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{self, RecvTimeoutError};
+
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelling_kills_the_command_quietly() {
+        let command = QuickCommand {
+            name: "test sleep".to_string(),
+            program: "sleep".to_string(),
+            args: vec!["10".to_string()],
+            key: None,
+        };
+        let (tx, rx) = mpsc::channel();
+        let cancel = CancelToken::default();
+        spawn(&command, cancel.clone(), move |event| {
+            let _ = tx.send(event);
+        })
+        .unwrap();
+        cancel.cancel();
+        // The sender goes away with the command's threads, long before the
+        // sleep would end
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(5)).unwrap_err(),
+            RecvTimeoutError::Disconnected
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, process};
+
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bundle::{self, Bundle},
+    persist,
+};
+
+/// How long to wait for the server before falling back to the cached copy.
+const TIMEOUT_SECS: &str = "5";
+
+/// Organization defaults fetched from `config_url`.
+#[derive(Debug, Clone)]
+pub struct RemoteConfig {
+    pub url: String,
+    pub bundle: Bundle,
+    /// The server could not be reached and the last fetched copy was used.
+    pub offline: bool,
+}
+
+/// The last verified copy, revalidated with its ETag on the next start.
+#[derive(Debug, Serialize, Deserialize)]
+struct Cached {
+    url: String,
+    etag: Option<String>,
+    contents: String,
+}
+
+/// Fetch the signed bundle at `url` and verify it against the `trusted`
+/// signers, reusing the cached copy if the server says it is unchanged or
+/// cannot be reached.
+///
+/// # Errors
+///
+/// This function will return an error if `url` is not HTTPS, if the bundle
+/// cannot be fetched and was never cached, or if it does not verify.
+pub fn fetch(url: &str, trusted: &BTreeMap<String, String>) -> color_eyre::Result<RemoteConfig> {
+    if !url.starts_with("https://") {
+        bail!("config_url must use https, got `{}`.", url);
+    }
+    let cached = cache_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| toml::from_str::<Cached>(&contents).ok())
+        .filter(|cached| cached.url == url);
+
+    let etag = cached.as_ref().and_then(|cached| cached.etag.as_deref());
+    let response = download(url, etag).and_then(|response| match response.status {
+        200 | 304 => Ok(response),
+        status => Err(eyre!("{} answered with HTTP {}", url, status)),
+    });
+    let (contents, offline) = match (response, cached) {
+        (Ok(response), _) if response.status == 200 => {
+            // Only a copy that verifies replaces the cached one
+            bundle::verify("remote", &response.body, trusted)?;
+            let fresh = Cached {
+                url: url.to_string(),
+                etag: response.etag,
+                contents: response.body,
+            };
+            if let Some(path) = cache_path() {
+                let _ = persist::write(&path, toml::to_string(&fresh)?);
+            }
+            (fresh.contents, false)
+        }
+        // Not modified, or the server could not be reached
+        (response, Some(cached)) => (cached.contents, response.is_err()),
+        (Ok(_), None) => bail!("{} answered 304 Not Modified to a first request", url),
+        (Err(err), None) => return Err(err),
+    };
+
+    let mut bundle = bundle::verify("remote", &contents, trusted)?;
+    // Where the defaults come from is the user's decision
+    bundle.payload.remove("config_url");
+    Ok(RemoteConfig {
+        url: url.to_string(),
+        bundle,
+        offline,
+    })
+}
+
+/// Where the last fetched copy is kept, e.g.
+/// `~/.cache/monika/work/remote_config.toml`.
+fn cache_path() -> Option<PathBuf> {
+    persist::profile_cache_dir().map(|dir| dir.join("remote_config.toml"))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Response {
+    status: u16,
+    etag: Option<String>,
+    body: String,
+}
+
+/// GET `url` with curl, which is at hand on every platform we support and
+/// spares us a TLS stack. Redirects are not followed.
+fn download(url: &str, etag: Option<&str>) -> color_eyre::Result<Response> {
+    let mut command = process::Command::new("curl");
+    command.args([
+        "--silent",
+        "--show-error",
+        "--proto",
+        "=https",
+        "--max-time",
+        TIMEOUT_SECS,
+        "--dump-header",
+        "-",
+    ]);
+    if let Some(etag) = etag {
+        command
+            .arg("--header")
+            .arg(format!("If-None-Match: {}", etag));
+    }
+    let output = command
+        .arg(url)
+        .output()
+        .wrap_err("Could not run curl to fetch config_url")?;
+    if !output.status.success() {
+        bail!(
+            "Could not fetch {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let raw = String::from_utf8(output.stdout)
+        .wrap_err_with(|| format!("{} did not answer with text", url))?;
+    parse_response(&raw).ok_or_else(|| eyre!("{} sent a malformed response", url))
+}
+
+/// Split curl's `--dump-header -` output into the final status, ETag and
+/// body, skipping e.g. a proxy's `Connection established`.
+fn parse_response(raw: &str) -> Option<Response> {
+    let (mut headers, mut body) = raw.split_once("\r\n\r\n")?;
+    while body.starts_with("HTTP/") {
+        (headers, body) = body.split_once("\r\n\r\n")?;
+    }
+    let mut lines = headers.lines();
+    let status = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+    let etag = lines.find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("etag")
+            .then(|| value.trim().to_string())
+    });
+    Some(Response {
+        status,
+        etag,
+        body: body.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_curl_responses() {
+        let raw = "HTTP/1.1 200 Connection established\r\n\r\n\
+                   HTTP/2 200\r\ncontent-type: text/plain\r\nETag: \"v2\"\r\n\r\n\
+                   payload = ''";
+        assert_eq!(
+            parse_response(raw),
+            Some(Response {
+                status: 200,
+                etag: Some("\"v2\"".to_string()),
+                body: "payload = ''".to_string(),
+            })
+        );
+        let not_modified = parse_response("HTTP/1.1 304 Not Modified\r\n\r\n").unwrap();
+        assert_eq!((not_modified.status, not_modified.etag), (304, None));
+        assert_eq!(parse_response("garbage"), None);
+    }
+}
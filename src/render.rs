@@ -0,0 +1,177 @@
+use ratatui::{
+    buffer::Buffer,
+    crossterm::{
+        style::{Color as CColor, ResetColor, SetBackgroundColor, SetForegroundColor},
+        Command,
+    },
+    style::Color,
+};
+
+/// The buffer's text, one line per row, without trailing whitespace.
+pub fn to_plain(buffer: &Buffer) -> String {
+    rows(buffer)
+        .map(|row| {
+            row.iter()
+                .map(|(_, symbol)| *symbol)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The buffer's text with ANSI color escapes, for terminals and CI logs.
+pub fn to_ansi(buffer: &Buffer) -> String {
+    let mut out = String::new();
+    for row in rows(buffer) {
+        let (mut fg, mut bg) = (Color::Reset, Color::Reset);
+        for ((cell_fg, cell_bg), symbol) in row {
+            // Writing escapes to a String cannot fail
+            if cell_fg != fg {
+                let _ = SetForegroundColor(CColor::from(cell_fg)).write_ansi(&mut out);
+                fg = cell_fg;
+            }
+            if cell_bg != bg {
+                let _ = SetBackgroundColor(CColor::from(cell_bg)).write_ansi(&mut out);
+                bg = cell_bg;
+            }
+            out.push_str(symbol);
+        }
+        let _ = ResetColor.write_ansi(&mut out);
+        out.push('\n');
+    }
+    out
+}
+
+/// Page background and default text color of exported documents.
+const EXPORT_BACKGROUND: &str = "#1e1e1e";
+const EXPORT_FOREGROUND: &str = "#d4d4d4";
+
+/// The buffer as a standalone HTML page, keeping the theme's colors.
+pub fn to_html(buffer: &Buffer) -> String {
+    html_page(&format!(
+        "<pre style=\"font-family:monospace\">\n{}</pre>",
+        to_html_fragment(buffer)
+    ))
+}
+
+/// Wrap `body` in a page using the export colors.
+pub fn html_page(body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>monika dashboard</title>\n</head>\n\
+        <body style=\"background:{bg};color:{fg}\">\n{body}\n</body>\n</html>\n",
+        bg = EXPORT_BACKGROUND,
+        fg = EXPORT_FOREGROUND,
+        body = body
+    )
+}
+
+/// The buffer's rows as colored HTML spans, to be placed inside a `<pre>`.
+pub fn to_html_fragment(buffer: &Buffer) -> String {
+    let mut body = String::new();
+    for row in rows(buffer) {
+        for (fg, text) in runs(row) {
+            match css_color(fg) {
+                Some(color) => body.push_str(&format!(
+                    "<span style=\"color:{}\">{}</span>",
+                    color,
+                    escape(&text)
+                )),
+                None => body.push_str(&escape(&text)),
+            }
+        }
+        body.push('\n');
+    }
+    body
+}
+
+/// The buffer as a standalone SVG image, keeping the theme's colors.
+pub fn to_svg(buffer: &Buffer) -> String {
+    // Monospace cells at 14px are roughly 8.4px wide and 17px tall
+    const CELL_WIDTH_TENTHS: u32 = 84;
+    const LINE_HEIGHT: u32 = 17;
+
+    let area = buffer.area;
+    let mut lines = String::new();
+    for (y, row) in rows(buffer).enumerate() {
+        lines.push_str(&format!(
+            "<text x=\"0\" y=\"{}\" xml:space=\"preserve\">",
+            (y as u32 + 1) * LINE_HEIGHT
+        ));
+        for (fg, text) in runs(row) {
+            let fill = css_color(fg).unwrap_or_else(|| EXPORT_FOREGROUND.to_string());
+            lines.push_str(&format!(
+                "<tspan fill=\"{}\">{}</tspan>",
+                fill,
+                escape(&text)
+            ));
+        }
+        lines.push_str("</text>\n");
+    }
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+        font-family=\"monospace\" font-size=\"14\">\n<rect width=\"100%\" height=\"100%\" fill=\"{bg}\"/>\n{lines}</svg>\n",
+        w = u32::from(area.width) * CELL_WIDTH_TENTHS / 10,
+        h = u32::from(area.height) * LINE_HEIGHT,
+        bg = EXPORT_BACKGROUND,
+        lines = lines
+    )
+}
+
+/// Split a row into stretches of text sharing a foreground color.
+fn runs(row: Row<'_>) -> Vec<(Color, String)> {
+    let mut runs: Vec<(Color, String)> = Vec::new();
+    for ((fg, _), symbol) in row {
+        match runs.last_mut() {
+            Some((color, text)) if *color == fg => text.push_str(symbol),
+            _ => runs.push((fg, symbol.to_string())),
+        }
+    }
+    runs
+}
+
+fn css_color(color: Color) -> Option<String> {
+    let named = match color {
+        Color::Rgb(r, g, b) => return Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        Color::Black => "black",
+        Color::Red => "#cd3131",
+        Color::Green => "#0dbc79",
+        Color::Yellow => "#e5e510",
+        Color::Blue => "#2472c8",
+        Color::Magenta => "#bc3fbc",
+        Color::Cyan => "#11a8cd",
+        Color::Gray => "#e5e5e5",
+        Color::DarkGray => "#666666",
+        Color::LightRed => "#f14c4c",
+        Color::LightGreen => "#23d18b",
+        Color::LightYellow => "#f5f543",
+        Color::LightBlue => "#3b8eea",
+        Color::LightMagenta => "#d670d6",
+        Color::LightCyan => "#29b8db",
+        Color::White => "white",
+        Color::Reset | Color::Indexed(_) => return None,
+    };
+    Some(named.to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+type Row<'a> = Vec<((Color, Color), &'a str)>;
+
+fn rows(buffer: &Buffer) -> impl Iterator<Item = Row<'_>> {
+    let area = buffer.area;
+    (area.top()..area.bottom()).map(move |y| {
+        (area.left()..area.right())
+            .map(|x| {
+                let cell = &buffer[(x, y)];
+                ((cell.fg, cell.bg), cell.symbol())
+            })
+            .collect()
+    })
+}
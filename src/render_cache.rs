@@ -0,0 +1,72 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use ratatui::{buffer::Buffer, layout::Rect};
+
+/// Drawings of components from earlier frames, reused while their inputs
+/// stay the same.
+///
+/// Each component is identified by name and keyed by a hash of everything
+/// it is drawn from; the area is always part of the key.
+#[derive(Debug, Default)]
+pub struct RenderCache {
+    entries: HashMap<&'static str, (u64, Buffer)>,
+}
+
+impl RenderCache {
+    /// Draw component `name` into `area` of `buf` with `draw`, or copy its
+    /// last drawing if `inputs` and `area` are unchanged.
+    ///
+    /// `draw` must cover all of `area`, and may only depend on `inputs`.
+    pub fn render(
+        &mut self,
+        name: &'static str,
+        inputs: impl Hash,
+        area: Rect,
+        buf: &mut Buffer,
+        draw: impl FnOnce(Rect, &mut Buffer),
+    ) {
+        let mut hasher = DefaultHasher::new();
+        (inputs, area).hash(&mut hasher);
+        let key = hasher.finish();
+
+        if self
+            .entries
+            .get(name)
+            .is_none_or(|(cached_key, _)| *cached_key != key)
+        {
+            let mut drawn = Buffer::empty(area);
+            draw(area, &mut drawn);
+            self.entries.insert(name, (key, drawn));
+        }
+        let (_, cached) = &self.entries[name];
+        for position in area.intersection(buf.area).positions() {
+            buf[position] = cached[position].clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::widgets::{Paragraph, Widget};
+
+    use super::*;
+
+    #[test]
+    fn redraws_only_when_inputs_change() {
+        let mut cache = RenderCache::default();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 2));
+        let area = Rect::new(2, 1, 5, 1);
+        let mut draws = 0;
+        for text in ["hello", "hello", "world"] {
+            cache.render("greeting", text, area, &mut buf, |area, buf| {
+                draws += 1;
+                Paragraph::new(text).render(area, buf);
+            });
+        }
+        assert_eq!(draws, 2);
+        assert_eq!(buf, Buffer::with_lines(["          ", "  world   "]));
+    }
+}
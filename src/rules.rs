@@ -0,0 +1,166 @@
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer};
+
+use crate::theme::Role;
+
+/// How concerning a value is, ordered from least to most severe.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Level {
+    #[default]
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl Level {
+    pub fn role(self) -> Role {
+        match self {
+            Level::Normal => Role::Info,
+            Level::Warning => Role::Warning,
+            Level::Critical => Role::Error,
+        }
+    }
+}
+
+/// Threshold rules for a value, configured e.g. as
+///
+/// ```toml
+/// [counter]
+/// warn_above = 40
+/// crit_above = 48
+/// alert = true
+/// ```
+///
+/// A value strictly beyond a bound reaches that level.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Thresholds {
+    pub warn_above: Option<i32>,
+    pub crit_above: Option<i32>,
+    pub warn_below: Option<i32>,
+    pub crit_below: Option<i32>,
+    /// Raise an alert when the value enters a more severe level.
+    pub alert: bool,
+}
+
+impl Thresholds {
+    pub fn evaluate(&self, value: i32) -> Level {
+        let beyond = |above: Option<i32>, below: Option<i32>| {
+            above.is_some_and(|bound| value > bound) || below.is_some_and(|bound| value < bound)
+        };
+        if beyond(self.crit_above, self.crit_below) {
+            Level::Critical
+        } else if beyond(self.warn_above, self.warn_below) {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+
+    /// The level `to` should alert about, if moving from `from` escalates.
+    pub fn alert(&self, from: i32, to: i32) -> Option<Level> {
+        let (before, after) = (self.evaluate(from), self.evaluate(to));
+        (self.alert && after > before).then_some(after)
+    }
+}
+
+/// What happens when the counter passes its bound.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BoundPolicy {
+    /// Warn and ask before resetting to 0.
+    #[default]
+    Confirm,
+    /// Reset to 0 right away.
+    Reset,
+    /// Stop at the bound.
+    Clamp,
+}
+
+/// How far the counter may go either way, configured e.g. as
+///
+/// ```toml
+/// [counter_bound]
+/// limit = 50
+/// policy = "clamp"
+/// ```
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Bound {
+    /// The counter is out of bounds beyond this value, or below its negation.
+    /// At least 1.
+    #[serde(deserialize_with = "positive")]
+    #[schemars(range(min = 1))]
+    pub limit: i32,
+    pub policy: BoundPolicy,
+}
+
+impl Default for Bound {
+    fn default() -> Self {
+        Self {
+            limit: 50,
+            policy: BoundPolicy::default(),
+        }
+    }
+}
+
+impl Bound {
+    pub fn contains(&self, value: i32) -> bool {
+        (-self.limit..=self.limit).contains(&value)
+    }
+}
+
+fn positive<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+    let limit = i32::deserialize(deserializer)?;
+    if limit < 1 {
+        return Err(de::Error::custom(format!(
+            "invalid limit {}, expected at least 1",
+            limit
+        )));
+    }
+    Ok(limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: Thresholds = Thresholds {
+        warn_above: Some(40),
+        crit_above: Some(48),
+        warn_below: Some(-40),
+        crit_below: None,
+        alert: true,
+    };
+
+    #[test]
+    fn evaluates_levels() {
+        assert_eq!(RULES.evaluate(40), Level::Normal);
+        assert_eq!(RULES.evaluate(41), Level::Warning);
+        assert_eq!(RULES.evaluate(49), Level::Critical);
+        assert_eq!(RULES.evaluate(-41), Level::Warning);
+        assert_eq!(RULES.evaluate(-1000), Level::Warning);
+    }
+
+    #[test]
+    fn alerts_only_on_escalation() {
+        assert_eq!(RULES.alert(40, 41), Some(Level::Warning));
+        assert_eq!(RULES.alert(41, 42), None);
+        assert_eq!(RULES.alert(49, 41), None);
+        let quiet = Thresholds {
+            alert: false,
+            ..RULES
+        };
+        assert_eq!(quiet.alert(40, 41), None);
+    }
+
+    #[test]
+    fn rejects_limits_below_one() {
+        for limit in ["0", "-5", "-2147483648"] {
+            let bound = toml::from_str::<Bound>(&format!("limit = {}", limit));
+            assert!(bound.is_err(), "limit {} was accepted", limit);
+        }
+        let bound: Bound = toml::from_str("limit = 1\npolicy = \"clamp\"").unwrap();
+        assert!(bound.contains(-1) && !bound.contains(2));
+    }
+}
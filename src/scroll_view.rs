@@ -0,0 +1,154 @@
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthChar;
+
+/// Columns panned per `h`/`l` press.
+pub const PAN_COLUMNS: usize = 8;
+
+/// How a scrolling pane shows lines longer than it is wide: soft-wrapped
+/// onto several rows, or cut off and panned horizontally.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
+pub struct ScrollView {
+    /// Rows scrolled up from the tail; 0 follows new lines.
+    pub scroll: usize,
+    pub wrap: bool,
+    /// Columns panned to the right, when not wrapping.
+    pub column: usize,
+}
+
+/// A screen row, showing the bytes `range` of `line`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Row<'a> {
+    /// Index of the line among all lines.
+    pub index: usize,
+    pub line: &'a str,
+    pub range: Range<usize>,
+}
+
+impl Row<'_> {
+    pub fn text(&self) -> &str {
+        &self.line[self.range.clone()]
+    }
+}
+
+impl ScrollView {
+    pub fn toggle_wrap(&mut self) {
+        self.wrap = !self.wrap;
+        self.column = 0;
+    }
+
+    /// Pan by `delta` columns; a no-op while wrapping.
+    pub fn pan(&mut self, delta: isize) {
+        if !self.wrap {
+            self.column = self.column.saturating_add_signed(delta);
+        }
+    }
+
+    /// The rows of `lines` that fit `height` rows of `width` columns,
+    /// clamping the scroll position to the first row.
+    pub fn rows<'a>(&mut self, lines: &'a [String], width: usize, height: usize) -> Vec<Row<'a>> {
+        let width = width.max(1);
+        let rows: Vec<Row> = if self.wrap {
+            lines
+                .iter()
+                .enumerate()
+                .flat_map(|(index, line)| wrap(index, line, width))
+                .collect()
+        } else {
+            lines
+                .iter()
+                .enumerate()
+                .map(|(index, line)| Row {
+                    index,
+                    line,
+                    range: columns(line, self.column, width),
+                })
+                .collect()
+        };
+        self.scroll = self.scroll.min(rows.len().saturating_sub(height));
+        let top = rows.len().saturating_sub(height) - self.scroll;
+        rows.into_iter().skip(top).take(height).collect()
+    }
+}
+
+/// `line` split into rows of at most `width` columns.
+fn wrap(index: usize, line: &str, width: usize) -> Vec<Row<'_>> {
+    let mut rows = Vec::new();
+    let (mut start, mut used) = (0, 0);
+    for (i, c) in line.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width > width && i > start {
+            rows.push(Row {
+                index,
+                line,
+                range: start..i,
+            });
+            (start, used) = (i, 0);
+        }
+        used += char_width;
+    }
+    rows.push(Row {
+        index,
+        line,
+        range: start..line.len(),
+    });
+    rows
+}
+
+/// The bytes of `line` covering `width` columns from column `skip`, leaving
+/// out characters cut by either edge.
+fn columns(line: &str, skip: usize, width: usize) -> Range<usize> {
+    let (mut start, mut end) = (line.len(), line.len());
+    let mut column = 0;
+    for (i, c) in line.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        if column >= skip && start == line.len() {
+            start = i;
+        }
+        if column + char_width > skip + width {
+            end = i;
+            break;
+        }
+        column += char_width;
+    }
+    start.min(end)..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(rows: &'a [Row<'a>]) -> Vec<&'a str> {
+        rows.iter().map(Row::text).collect()
+    }
+
+    #[test]
+    fn wraps_long_lines_keeping_the_tail() {
+        let lines = vec!["abcdefgh".to_string(), "ij".to_string()];
+        let mut view = ScrollView {
+            wrap: true,
+            ..ScrollView::default()
+        };
+        assert_eq!(texts(&view.rows(&lines, 3, 2)), vec!["gh", "ij"]);
+        view.scroll = 10;
+        assert_eq!(texts(&view.rows(&lines, 3, 2)), vec!["abc", "def"]);
+        assert_eq!(view.scroll, 2);
+        // Wide characters never straddle rows
+        let wide = vec!["a日本".to_string()];
+        assert_eq!(texts(&view.rows(&wide, 2, 5)), vec!["a", "日", "本"]);
+    }
+
+    #[test]
+    fn pans_horizontally() {
+        let lines = vec!["abcdefgh".to_string(), "ij".to_string()];
+        let mut view = ScrollView::default();
+        assert_eq!(texts(&view.rows(&lines, 3, 5)), vec!["abc", "ij"]);
+        view.pan(4);
+        assert_eq!(texts(&view.rows(&lines, 3, 5)), vec!["efg", ""]);
+        view.pan(-10);
+        assert_eq!(view.column, 0);
+        view.toggle_wrap();
+        view.pan(4);
+        assert_eq!(view.column, 0);
+    }
+}
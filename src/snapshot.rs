@@ -0,0 +1,194 @@
+use std::{env, fs, path::Path};
+
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+
+use crate::{capabilities::Capabilities, config::Config, keymap, persist};
+
+/// Settings that may hold secrets and are left out of snapshots, as dotted
+/// paths where `*` matches every entry of a table or list. Command lines and
+/// addresses may embed tokens.
+const REDACTED: [&str; 4] = [
+    "config_url",
+    "bell.sound_command",
+    "profiles.*.credential_helper",
+    "quick_commands.*.args",
+];
+
+/// Everything needed to start the app the way a user saw it, minus secrets.
+///
+/// Written by `monika snapshot` for bug reports and read back by
+/// `monika run --from-snapshot`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub versions: Versions,
+    pub terminal: Terminal,
+    /// The keys of the main screen and what they do.
+    #[serde(default)]
+    pub keymap: Vec<Binding>,
+    /// Settings that were removed, as dotted paths.
+    #[serde(default)]
+    pub redacted: Vec<String>,
+    /// The merged config, without the redacted settings.
+    pub config: toml::Table,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Versions {
+    pub monika: String,
+    pub os: String,
+    pub arch: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Terminal {
+    pub key: String,
+    pub capabilities: Capabilities,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Binding {
+    pub key: String,
+    pub action: String,
+}
+
+impl Snapshot {
+    pub fn capture(config: &Config, terminal: Terminal) -> Self {
+        let keymap = keymap::bindings(&config.quick_commands)
+            .into_iter()
+            .map(|(key, action)| Binding { key, action })
+            .collect();
+        let mut config = toml::Value::Table(config.table.clone());
+        let mut redacted = Vec::new();
+        for path in REDACTED {
+            let path: Vec<&str> = path.split('.').collect();
+            redact(&mut config, &path, "", &mut redacted);
+        }
+        let toml::Value::Table(config) = config else {
+            unreachable!("redacting keeps the table");
+        };
+        Self {
+            versions: Versions {
+                monika: env!("CARGO_PKG_VERSION").to_string(),
+                os: env::consts::OS.to_string(),
+                arch: env::consts::ARCH.to_string(),
+            },
+            terminal,
+            keymap,
+            redacted,
+            config,
+        }
+    }
+
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be written.
+    pub fn write(&self, path: &Path) -> color_eyre::Result<()> {
+        let contents = toml::to_string(self).wrap_err("Could not serialize snapshot")?;
+        persist::atomic_write(path, contents)
+            .wrap_err_with(|| format!("Could not write {}", path.display()))
+    }
+
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be read or is
+    /// not a snapshot.
+    pub fn read(path: &Path) -> color_eyre::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Could not read {}", path.display()))?;
+        toml::from_str(&contents).wrap_err_with(|| format!("Invalid snapshot {}", path.display()))
+    }
+}
+
+/// Remove the settings at `path` below `value`, recording where they were
+/// under `prefix`.
+fn redact(value: &mut toml::Value, path: &[&str], prefix: &str, redacted: &mut Vec<String>) {
+    let Some((key, rest)) = path.split_first() else {
+        return;
+    };
+    let join = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        }
+    };
+    let children: Vec<(String, &mut toml::Value)> = match value {
+        toml::Value::Table(table) if rest.is_empty() => {
+            if table.remove(*key).is_some() {
+                redacted.push(join(key));
+            }
+            return;
+        }
+        toml::Value::Table(table) if *key == "*" => {
+            table.iter_mut().map(|(name, v)| (join(name), v)).collect()
+        }
+        toml::Value::Array(array) if *key == "*" => array
+            .iter_mut()
+            .enumerate()
+            .map(|(index, v)| (join(&index.to_string()), v))
+            .collect(),
+        toml::Value::Table(table) => table
+            .get_mut(*key)
+            .map(|v| (join(key), v))
+            .into_iter()
+            .collect(),
+        _ => return,
+    };
+    for (path_so_far, child) in children {
+        redact(child, rest, &path_so_far, redacted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_settings_that_may_hold_secrets() {
+        let config = Config::from_table(
+            toml::from_str(
+                "theme = \"protanopia\"\n\
+                 config_url = \"https://token@example.com/defaults\"\n\
+                 [bell]\nsound_command = \"play --key abc\"\n\
+                 [profiles.work]\ncredential_helper = \"get-key --token abc\"\nnetrc_machine = \"api\"\n\
+                 [[quick_commands]]\nname = \"ping\"\nprogram = \"curl\"\nargs = [\"-H\", \"token: abc\"]\n",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let terminal = Terminal {
+            key: "xterm-256color".to_string(),
+            capabilities: Capabilities::default(),
+        };
+
+        let snapshot = Snapshot::capture(&config, terminal);
+        assert_eq!(
+            snapshot.redacted,
+            vec![
+                "config_url",
+                "bell.sound_command",
+                "profiles.work.credential_helper",
+                "quick_commands.0.args",
+            ]
+        );
+        assert!(snapshot
+            .keymap
+            .iter()
+            .any(|binding| binding.key == "1" && binding.action == "run `ping`"));
+
+        let written = toml::to_string(&snapshot).unwrap();
+        assert!(!written.contains("abc"));
+        assert!(!written.contains("token"));
+        let read: Snapshot = toml::from_str(&written).unwrap();
+        assert_eq!(
+            read.config["profiles"]["work"]["netrc_machine"].as_str(),
+            Some("api")
+        );
+        assert_eq!(
+            read.config["quick_commands"][0]["program"].as_str(),
+            Some("curl")
+        );
+        assert_eq!(read.config["theme"].as_str(), Some("protanopia"));
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Finished tasks kept for the debug screen; older ones are forgotten.
+const FINISHED_KEPT: usize = 100;
+
+/// Every background task of the process, for inspection and for spotting
+/// tasks that outlive shutdown.
+static REGISTRY: Mutex<Vec<Task>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum State {
+    Running,
+    Finished,
+    Panicked,
+}
+
+/// A background thread, as recorded when it was spawned.
+#[derive(Debug, Clone)]
+pub struct Task {
+    id: u64,
+    pub name: String,
+    pub state: State,
+    pub started: Instant,
+    pub ended: Option<Instant>,
+    /// Expected to run until the process exits, e.g. a server.
+    pub service: bool,
+}
+
+/// Marks its task as ended when the thread is done, however it ends.
+struct Guard(u64);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let state = if thread::panicking() {
+            State::Panicked
+        } else {
+            State::Finished
+        };
+        update(|tasks| {
+            if let Some(task) = tasks.iter_mut().find(|task| task.id == self.0) {
+                task.state = state;
+                task.ended = Some(Instant::now());
+            }
+        });
+    }
+}
+
+/// Run `f` on a named, tracked thread that should end before shutdown.
+///
+/// # Panics
+///
+/// Panics if the thread cannot be created, like [`thread::spawn`].
+pub fn spawn<F, T>(name: impl Into<String>, f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    start(name.into(), false, f)
+}
+
+/// Run `f` on a named, tracked thread that may run until the process exits.
+///
+/// # Panics
+///
+/// Panics if the thread cannot be created, like [`thread::spawn`].
+pub fn spawn_service<F, T>(name: impl Into<String>, f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    start(name.into(), true, f)
+}
+
+fn start<F, T>(name: String, service: bool, f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    update(|tasks| {
+        tasks.push(Task {
+            id,
+            name: name.clone(),
+            state: State::Running,
+            started: Instant::now(),
+            ended: None,
+            service,
+        });
+        let finished = tasks.iter().filter(|t| t.state != State::Running).count();
+        let mut excess = finished.saturating_sub(FINISHED_KEPT);
+        tasks.retain(|task| {
+            let forget = excess > 0 && task.state != State::Running;
+            excess -= usize::from(forget);
+            !forget
+        });
+    });
+    thread::Builder::new()
+        .name(name)
+        .spawn(move || {
+            let _guard = Guard(id);
+            f()
+        })
+        .expect("failed to spawn thread")
+}
+
+/// Asks background work to stop, e.g. because the app quits or the pane it
+/// reports to moved on. Work checks it between steps and stops sending
+/// messages once it is cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<CancelState>);
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    parent: Option<CancelToken>,
+}
+
+impl CancelToken {
+    /// A token that is also cancelled along with this one.
+    pub fn child(&self) -> Self {
+        Self(Arc::new(CancelState {
+            cancelled: AtomicBool::new(false),
+            parent: Some(self.clone()),
+        }))
+    }
+
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+            || self.0.parent.as_ref().is_some_and(Self::is_cancelled)
+    }
+
+    /// Cancel this token when the returned guard goes out of scope, however
+    /// the scope is left.
+    pub fn cancel_on_drop(&self) -> CancelGuard {
+        CancelGuard(self.clone())
+    }
+}
+
+pub struct CancelGuard(CancelToken);
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+fn update(f: impl FnOnce(&mut Vec<Task>)) {
+    // A panic while holding the lock leaves the list itself intact
+    let mut tasks = REGISTRY.lock().unwrap_or_else(|err| err.into_inner());
+    f(&mut tasks);
+}
+
+/// All known tasks, oldest first.
+pub fn list() -> Vec<Task> {
+    let mut tasks = Vec::new();
+    update(|registry| tasks = registry.clone());
+    tasks
+}
+
+/// Wait up to `grace` for tasks that should end before shutdown, returning
+/// those still running.
+pub fn outliving(grace: Duration) -> Vec<Task> {
+    let deadline = Instant::now() + grace;
+    loop {
+        let running: Vec<Task> = list()
+            .into_iter()
+            .filter(|task| task.state == State::Running && !task.service)
+            .collect();
+        if running.is_empty() || Instant::now() >= deadline {
+            return running;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_of(name: &str) -> Option<State> {
+        list()
+            .into_iter()
+            .find(|task| task.name == name)
+            .map(|task| task.state)
+    }
+
+    #[test]
+    fn records_how_tasks_end() {
+        spawn("test finishes", || 1).join().unwrap();
+        assert_eq!(state_of("test finishes"), Some(State::Finished));
+
+        assert!(spawn("test panics", || panic!("expected")).join().is_err());
+        assert_eq!(state_of("test panics"), Some(State::Panicked));
+
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let waiting = spawn("test outlives", move || rx.recv());
+        let outliving = outliving(Duration::from_millis(20));
+        assert!(outliving.iter().any(|task| task.name == "test outlives"));
+        drop(tx);
+        waiting.join().unwrap().unwrap_err();
+    }
+
+    #[test]
+    fn cancels_children_with_their_parent() {
+        let app = CancelToken::default();
+        let (first, second) = (app.child(), app.child());
+        first.cancel();
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled() && !app.is_cancelled());
+
+        drop(app.cancel_on_drop());
+        assert!(second.is_cancelled());
+    }
+}
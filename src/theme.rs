@@ -0,0 +1,180 @@
+use ratatui::style::{Color, Style};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// What a piece of UI means, independent of how it is colored.
+///
+/// Views ask the theme for a role instead of hard-coding colors, so a preset
+/// can remap every role at once (e.g. for color vision deficiencies).
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Role {
+    Success,
+    Warning,
+    Error,
+    Info,
+    Accent,
+}
+
+/// Built-in themes selectable through the `theme` setting.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemePreset {
+    #[default]
+    Default,
+    Deuteranopia,
+    Protanopia,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Theme {
+    success: Color,
+    warning: Color,
+    error: Color,
+    info: Color,
+    accent: Color,
+}
+
+impl Theme {
+    pub fn from_preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Default => Self {
+                success: Color::Rgb(0x4e, 0xc9, 0x4e),
+                warning: Color::Rgb(0xe5, 0xc0, 0x3b),
+                error: Color::Rgb(0xe0, 0x4b, 0x4b),
+                info: Color::Rgb(0x4f, 0xb3, 0xd9),
+                accent: Color::Rgb(0xc6, 0x78, 0xdd),
+            },
+            // Both presets draw from the Okabe-Ito palette. Success and error
+            // sit on the blue/orange axis, which red-green deficiencies keep.
+            ThemePreset::Deuteranopia => Self {
+                success: Color::Rgb(0x00, 0x72, 0xb2),
+                warning: Color::Rgb(0xf0, 0xe4, 0x42),
+                error: Color::Rgb(0xd5, 0x5e, 0x00),
+                info: Color::Rgb(0x56, 0xb4, 0xe9),
+                accent: Color::Rgb(0xcc, 0x79, 0xa7),
+            },
+            // Protanopes perceive long wavelengths as darker, so error uses
+            // the brighter orange instead of vermillion.
+            ThemePreset::Protanopia => Self {
+                success: Color::Rgb(0x00, 0x72, 0xb2),
+                warning: Color::Rgb(0xf0, 0xe4, 0x42),
+                error: Color::Rgb(0xe6, 0x9f, 0x00),
+                info: Color::Rgb(0x56, 0xb4, 0xe9),
+                accent: Color::Rgb(0xcc, 0x79, 0xa7),
+            },
+        }
+    }
+
+    pub fn color(&self, role: Role) -> Color {
+        match role {
+            Role::Success => self.success,
+            Role::Warning => self.warning,
+            Role::Error => self.error,
+            Role::Info => self.info,
+            Role::Accent => self.accent,
+        }
+    }
+
+    pub fn style(&self, role: Role) -> Style {
+        Style::default().fg(self.color(role))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::from_preset(ThemePreset::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_ROLES: [Role; 5] = [
+        Role::Success,
+        Role::Warning,
+        Role::Error,
+        Role::Info,
+        Role::Accent,
+    ];
+
+    // Machado, Oliveira & Fernandes (2009), severity 1.0, linear RGB.
+    const PROTANOPIA: [[f64; 3]; 3] = [
+        [0.152286, 1.052583, -0.204868],
+        [0.114503, 0.786281, 0.099216],
+        [-0.003882, -0.048116, 1.051998],
+    ];
+    const DEUTERANOPIA: [[f64; 3]; 3] = [
+        [0.367322, 0.860646, -0.227968],
+        [0.280085, 0.672501, 0.047413],
+        [-0.011820, 0.042940, 0.968881],
+    ];
+
+    /// Smallest CIE76 distance two roles may have after simulation.
+    const MIN_DELTA_E: f64 = 20.0;
+
+    fn to_linear(channel: u8) -> f64 {
+        let c = f64::from(channel) / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    fn simulate(color: Color, matrix: &[[f64; 3]; 3]) -> [f64; 3] {
+        let Color::Rgb(r, g, b) = color else {
+            panic!("theme colors must be RGB to be simulated, got {color:?}");
+        };
+        let rgb = [to_linear(r), to_linear(g), to_linear(b)];
+        matrix.map(|row| (row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]).clamp(0.0, 1.0))
+    }
+
+    fn to_lab([r, g, b]: [f64; 3]) -> [f64; 3] {
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+        let f = |t: f64| {
+            if t > 0.008856 {
+                t.cbrt()
+            } else {
+                7.787 * t + 16.0 / 116.0
+            }
+        };
+        [
+            116.0 * f(y) - 16.0,
+            500.0 * (f(x) - f(y)),
+            200.0 * (f(y) - f(z)),
+        ]
+    }
+
+    fn assert_roles_distinguishable(preset: ThemePreset, matrix: &[[f64; 3]; 3]) {
+        let theme = Theme::from_preset(preset);
+        for (i, a) in ALL_ROLES.iter().enumerate() {
+            for b in &ALL_ROLES[i + 1..] {
+                let la = to_lab(simulate(theme.color(*a), matrix));
+                let lb = to_lab(simulate(theme.color(*b), matrix));
+                let delta = la
+                    .iter()
+                    .zip(lb)
+                    .map(|(x, y)| (x - y).powi(2))
+                    .sum::<f64>()
+                    .sqrt();
+                assert!(
+                    delta >= MIN_DELTA_E,
+                    "{preset:?}: {a:?} and {b:?} are too similar (ΔE {delta:.1})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn deuteranopia_preset_survives_simulation() {
+        assert_roles_distinguishable(ThemePreset::Deuteranopia, &DEUTERANOPIA);
+    }
+
+    #[test]
+    fn protanopia_preset_survives_simulation() {
+        assert_roles_distinguishable(ThemePreset::Protanopia, &PROTANOPIA);
+    }
+}
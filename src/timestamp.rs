@@ -0,0 +1,181 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::persist;
+
+/// How timestamps are shown, switched with `T` and remembered across runs.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum TimeFormat {
+    /// How long ago, e.g. `12s ago`.
+    #[default]
+    Relative,
+    /// Wall clock time in the local time zone, e.g. `14:03:21`.
+    Local,
+    /// Wall clock time in UTC, e.g. `12:03:21Z`.
+    Utc,
+}
+
+impl TimeFormat {
+    const ALL: [Self; 3] = [Self::Relative, Self::Local, Self::Utc];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Relative => "relative",
+            Self::Local => "local time",
+            Self::Utc => "UTC",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Relative => "relative",
+            Self::Local => "local",
+            Self::Utc => "utc",
+        }
+    }
+
+    /// Show the moment `age` before now.
+    pub fn format(self, age: Duration) -> String {
+        let at = SystemTime::now().checked_sub(age).unwrap_or(UNIX_EPOCH);
+        match self {
+            Self::Relative => format_age(age),
+            Self::Local => format_clock(seconds_since_epoch(at) + utc_offset(at)),
+            Self::Utc => format!("{}Z", format_clock(seconds_since_epoch(at))),
+        }
+    }
+
+    /// The format chosen in an earlier run, if any.
+    pub fn load() -> Self {
+        let saved = preference_path().and_then(|path| fs::read_to_string(path).ok());
+        Self::ALL
+            .into_iter()
+            .find(|f| Some(f.name()) == saved.as_deref().map(str::trim))
+            .unwrap_or_default()
+    }
+
+    /// Remember this format for the next run.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the preference cannot be written.
+    pub fn save(self) -> std::io::Result<()> {
+        match preference_path() {
+            Some(path) => persist::write(&path, self.name()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Where the chosen format is kept, e.g.
+/// `~/.local/state/monika/work/time_format`.
+fn preference_path() -> Option<PathBuf> {
+    persist::profile_state_dir().map(|dir| dir.join("time_format"))
+}
+
+/// Render an age as a short "last updated" label, e.g. `12s ago`.
+pub fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        0 => "just now".to_string(),
+        secs @ 1..=59 => format!("{}s ago", secs),
+        secs @ 60..=3599 => format!("{}m ago", secs / 60),
+        secs @ 3600..=86_399 => format!("{}h ago", secs / 3600),
+        secs => format!("{}d ago", secs / 86_400),
+    }
+}
+
+/// Parse a duration like `90d`, `12h`, `30m` or `45s`.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let unit = match text.chars().last() {
+        Some('d') => 24 * 60 * 60,
+        Some('h') => 60 * 60,
+        Some('m') => 60,
+        Some('s') => 1,
+        _ => return Err("expected a number with a unit: d, h, m or s, e.g. `90d`".to_string()),
+    };
+    let count: u64 = text[..text.len() - 1]
+        .parse()
+        .map_err(|_| format!("`{}` does not start with a whole number", text))?;
+    count
+        .checked_mul(unit)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("`{}` is too long", text))
+}
+
+/// The time of day of `seconds` since the epoch, as `HH:MM:SS`.
+fn format_clock(seconds: i64) -> String {
+    let seconds = seconds.rem_euclid(24 * 60 * 60);
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn seconds_since_epoch(at: SystemTime) -> i64 {
+    at.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64)
+}
+
+/// Seconds the local time zone is ahead of UTC at `at`.
+#[cfg(unix)]
+fn utc_offset(at: SystemTime) -> i64 {
+    let at = seconds_since_epoch(at) as libc::time_t;
+    // SAFETY: `tm` is only read after `localtime_r` filled it in
+    let mut tm = unsafe { std::mem::zeroed::<libc::tm>() };
+    if unsafe { libc::localtime_r(&at, &mut tm) }.is_null() {
+        return 0;
+    }
+    tm.tm_gmtoff as i64
+}
+
+/// Lacking a time zone database, local time is UTC.
+#[cfg(not(unix))]
+fn utc_offset(_at: SystemTime) -> i64 {
+    0
+}
+
+/// Minutes since local midnight.
+pub fn local_minute_of_day() -> u16 {
+    let now = SystemTime::now();
+    let local = seconds_since_epoch(now) + utc_offset(now);
+    (local.rem_euclid(24 * 60 * 60) / 60) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(format_clock(0), "00:00:00");
+        assert_eq!(format_clock(86_399), "23:59:59");
+        assert_eq!(format_clock(-1), "23:59:59");
+        assert_eq!(
+            TimeFormat::Relative.format(Duration::from_secs(90)),
+            "1m ago"
+        );
+
+        let utc = TimeFormat::Utc.format(Duration::ZERO);
+        assert_eq!(utc.len(), 9);
+        assert!(utc.ends_with('Z'));
+        assert_eq!(TimeFormat::Utc.next(), TimeFormat::Relative);
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90d"), Ok(Duration::from_secs(90 * 86_400)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert!(parse_duration("90").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration(&format!("{}d", u64::MAX / 2)).is_err());
+    }
+}
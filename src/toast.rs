@@ -0,0 +1,28 @@
+use std::time::{Duration, Instant};
+
+use crate::theme::Role;
+
+/// How long a toast stays on screen.
+pub const TOAST_TTL: Duration = Duration::from_secs(4);
+
+/// A short-lived message shown in the status line.
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub role: Role,
+    pub text: String,
+    pub shown_at: Instant,
+}
+
+impl Toast {
+    pub fn new(role: Role, text: impl Into<String>, now: Instant) -> Self {
+        Self {
+            role,
+            text: text.into(),
+            shown_at: now,
+        }
+    }
+
+    pub fn is_expired(&self, now: Instant) -> bool {
+        now.duration_since(self.shown_at) >= TOAST_TTL
+    }
+}
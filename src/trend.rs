@@ -0,0 +1,129 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// How far back the velocity is measured.
+const WINDOW: Duration = Duration::from_secs(60);
+/// Minimum spacing between samples, so fast ticks don't flood the history.
+const SAMPLE_EVERY: Duration = Duration::from_secs(1);
+/// Number of samples drawn in the sparkline.
+const SPARKLINE_LEN: usize = 10;
+const SPARK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Recent history of a counter, kept just long enough to compute its trend.
+///
+/// Samples are appended on ticks and evicted from the front once they fall
+/// out of the window, so updating and querying never rescan the history.
+#[derive(Debug, Default, Clone)]
+pub struct Trend {
+    samples: VecDeque<(Instant, i32)>,
+}
+
+impl Trend {
+    pub fn record(&mut self, now: Instant, value: i32) {
+        if let Some((last, _)) = self.samples.back() {
+            if now.duration_since(*last) < SAMPLE_EVERY {
+                return;
+            }
+        }
+        self.samples.push_back((now, value));
+        while let Some((oldest, _)) = self.samples.front() {
+            if now.duration_since(*oldest) <= WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// Change per minute across the window, if there are two samples.
+    pub fn per_minute(&self) -> Option<f64> {
+        let (first_at, first) = self.samples.front()?;
+        let (last_at, last) = self.samples.back()?;
+        let elapsed = last_at.duration_since(*first_at).as_secs_f64();
+        if elapsed == 0.0 {
+            return None;
+        }
+        // Widened, as the change between two i32 may not fit one
+        let change = i64::from(*last) - i64::from(*first);
+        Some(change as f64 * 60.0 / elapsed)
+    }
+
+    pub fn arrow(&self) -> char {
+        match self.per_minute() {
+            Some(rate) if rate > 0.0 => '↑',
+            Some(rate) if rate < 0.0 => '↓',
+            _ => '→',
+        }
+    }
+
+    /// The last few samples as a unicode sparkline, scaled to their range.
+    pub fn sparkline(&self) -> String {
+        let recent: Vec<i64> = self
+            .samples
+            .iter()
+            .rev()
+            .take(SPARKLINE_LEN)
+            .map(|(_, value)| i64::from(*value))
+            .collect();
+        let (Some(min), Some(max)) = (recent.iter().min(), recent.iter().max()) else {
+            return String::new();
+        };
+        let range = (max - min).max(1) as u64;
+        recent
+            .iter()
+            .rev()
+            .map(|value| {
+                let level = (value - min) as u64 * (SPARK_BARS.len() as u64 - 1) / range;
+                SPARK_BARS[level as usize]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn velocity_over_window() {
+        let start = Instant::now();
+        let mut trend = Trend::default();
+        trend.record(start, 0);
+        trend.record(start + Duration::from_secs(30), 10);
+        assert_eq!(trend.per_minute(), Some(20.0));
+        assert_eq!(trend.arrow(), '↑');
+    }
+
+    #[test]
+    fn old_samples_leave_the_window() {
+        let start = Instant::now();
+        let mut trend = Trend::default();
+        trend.record(start, 100);
+        trend.record(start + Duration::from_secs(50), 0);
+        trend.record(start + Duration::from_secs(70), 0);
+        // The 100 sample is older than a minute, so the counter looks flat
+        assert_eq!(trend.per_minute(), Some(0.0));
+        assert_eq!(trend.arrow(), '→');
+    }
+
+    #[test]
+    fn samples_are_rate_limited() {
+        let start = Instant::now();
+        let mut trend = Trend::default();
+        trend.record(start, 1);
+        trend.record(start + Duration::from_millis(250), 2);
+        assert_eq!(trend.per_minute(), None);
+        assert_eq!(trend.sparkline(), "▁");
+    }
+
+    #[test]
+    fn extreme_values_do_not_overflow() {
+        let start = Instant::now();
+        let mut trend = Trend::default();
+        trend.record(start, i32::MIN);
+        trend.record(start + Duration::from_secs(30), i32::MAX);
+        assert_eq!(trend.arrow(), '↑');
+        assert_eq!(trend.sparkline(), "▁█");
+    }
+}
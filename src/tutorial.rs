@@ -0,0 +1,80 @@
+use crate::pane::PaneId;
+
+/// One step of the tutorial: what to do, where, and the message that shows
+/// it was done.
+#[derive(Debug)]
+pub struct Step<M: 'static> {
+    pub text: &'static str,
+    /// Pane to highlight while the step is shown.
+    pub target: Option<PaneId>,
+    pub done: fn(&M) -> bool,
+}
+
+/// Progress through a list of steps, advancing as the expected messages are
+/// seen.
+#[derive(Debug)]
+pub struct Tutorial<M: 'static> {
+    steps: &'static [Step<M>],
+    current: usize,
+}
+
+// Derived, these would require `M: Clone` for no reason
+impl<M> Clone for Tutorial<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for Tutorial<M> {}
+
+impl<M> Tutorial<M> {
+    pub fn new(steps: &'static [Step<M>]) -> Self {
+        Self { steps, current: 0 }
+    }
+
+    /// The step to show, or `None` once all are done.
+    pub fn step(&self) -> Option<&'static Step<M>> {
+        self.steps.get(self.current)
+    }
+
+    /// Progress as `current of total`, counting from one.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.current + 1, self.steps.len())
+    }
+
+    /// Advance if `msg` completes the current step.
+    pub fn observe(&mut self, msg: &M) {
+        if self.step().is_some_and(|step| (step.done)(msg)) {
+            self.current += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static STEPS: [Step<char>; 2] = [
+        Step {
+            text: "Press j",
+            target: Some(PaneId::Live),
+            done: |c| *c == 'j',
+        },
+        Step {
+            text: "Press k",
+            target: None,
+            done: |c| *c == 'k',
+        },
+    ];
+
+    #[test]
+    fn advances_on_expected_messages() {
+        let mut tutorial = Tutorial::new(&STEPS);
+        tutorial.observe(&'k');
+        assert_eq!(tutorial.step().map(|step| step.text), Some("Press j"));
+        tutorial.observe(&'j');
+        assert_eq!(tutorial.progress(), (2, 2));
+        tutorial.observe(&'k');
+        assert!(tutorial.step().is_none());
+    }
+}
@@ -0,0 +1,196 @@
+use std::ops::Range;
+
+use serde_json::Value;
+use toml::de::{DeTable, DeValue};
+
+use crate::config::Config;
+
+/// Something wrong with a config file, located by byte range.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub span: Range<usize>,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl Problem {
+    /// Render as `path:line:column: message`, plus the suggestion if any.
+    pub fn describe(&self, path: &str, contents: &str) -> String {
+        let (line, column) = line_column(contents, self.span.start);
+        let mut text = format!("{}:{}:{}: {}", path, line, column, self.message);
+        if let Some(suggestion) = &self.suggestion {
+            text.push_str(&format!("\n  help: {}", suggestion));
+        }
+        text
+    }
+}
+
+/// Check config file `contents` strictly: besides what loading rejects
+/// (syntax errors, wrong types, invalid values), unknown keys are reported,
+/// since they are otherwise silently ignored.
+pub fn check_config(contents: &str) -> Vec<Problem> {
+    let table = match DeTable::parse(contents) {
+        Ok(table) => table,
+        Err(err) => return vec![from_toml_error(&err)],
+    };
+
+    let schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default();
+    let mut problems = Vec::new();
+    check_table(table.get_ref(), &schema, &schema, "", &mut problems);
+
+    if let Err(err) = toml::from_str::<Config>(contents) {
+        problems.push(from_toml_error(&err));
+    }
+    problems.sort_by_key(|problem| problem.span.start);
+    problems
+}
+
+fn from_toml_error(err: &toml::de::Error) -> Problem {
+    Problem {
+        span: err.span().unwrap_or(0..0),
+        message: err.message().trim_end().to_string(),
+        suggestion: None,
+    }
+}
+
+/// Report keys of `table` that `schema` does not know, descending into
+/// nested tables and arrays of tables.
+fn check_table(
+    table: &DeTable<'_>,
+    schema: &Value,
+    root: &Value,
+    path: &str,
+    problems: &mut Vec<Problem>,
+) {
+    let schema = resolve(schema, root);
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let additional = schema.get("additionalProperties").filter(|v| v.is_object());
+
+    for (key, value) in table {
+        let name = key.get_ref().as_ref();
+        let nested = if path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", path, name)
+        };
+        let value_schema = match (properties.and_then(|p| p.get(name)), additional) {
+            (Some(value_schema), _) | (None, Some(value_schema)) => value_schema,
+            (None, None) => {
+                let known = properties.into_iter().flat_map(|p| p.keys());
+                problems.push(Problem {
+                    span: key.span(),
+                    message: format!("unknown key `{}`", nested),
+                    suggestion: closest(name, known).map(|key| format!("did you mean `{}`?", key)),
+                });
+                continue;
+            }
+        };
+        check_value(value.get_ref(), value_schema, root, &nested, problems);
+    }
+}
+
+fn check_value(
+    value: &DeValue<'_>,
+    schema: &Value,
+    root: &Value,
+    path: &str,
+    problems: &mut Vec<Problem>,
+) {
+    match value {
+        DeValue::Table(table) => check_table(table, schema, root, path, problems),
+        DeValue::Array(items) => {
+            if let Some(item_schema) = resolve(schema, root).get("items") {
+                for item in items {
+                    check_value(item.get_ref(), item_schema, root, path, problems);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Follow a `$ref` into the root schema's `$defs`.
+fn resolve<'a>(schema: &'a Value, root: &'a Value) -> &'a Value {
+    schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix("#/$defs/"))
+        .and_then(|name| root.get("$defs")?.get(name))
+        .unwrap_or(schema)
+}
+
+/// The known key closest to a misspelled `key`, if any is close enough.
+fn closest<'a>(key: &str, known: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    known
+        .map(|candidate| (edit_distance(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// One-based line and column of byte `offset` in `contents`.
+fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_unknown_keys_with_suggestions() {
+        let contents =
+            "theme = \"default\"\nthemes = 1\n\n[profiles.work]\ncredential_helpr = \"pass\"\n";
+        let problems = check_config(contents);
+        let described: Vec<String> = problems
+            .iter()
+            .map(|problem| problem.describe("config.toml", contents))
+            .collect();
+        assert_eq!(
+            described,
+            vec![
+                "config.toml:2:1: unknown key `themes`\n  help: did you mean `theme`?",
+                "config.toml:5:1: unknown key `profiles.work.credential_helpr`\n  \
+                 help: did you mean `credential_helper`?",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_type_mismatches() {
+        let problems = check_config("[counter]\nwarn_above = \"forty\"\n");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            line_column(
+                "[counter]\nwarn_above = \"forty\"\n",
+                problems[0].span.start
+            ),
+            (2, 14)
+        );
+    }
+
+    #[test]
+    fn accepts_valid_config() {
+        let contents =
+            "theme = \"deuteranopia\"\n\n[[quick_commands]]\nname = \"ls\"\nprogram = \"ls\"\n";
+        assert_eq!(check_config(contents), vec![]);
+    }
+}
@@ -0,0 +1,79 @@
+use std::{fs, path::PathBuf};
+
+use crate::persist;
+
+/// Release notes, newest first, as `## <version>` sections.
+const CHANGELOG: &str = include_str!("../CHANGELOG.md");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Where the version of the last run is kept, e.g.
+/// `~/.local/state/monika/work/last_version`.
+fn last_version_path() -> Option<PathBuf> {
+    persist::profile_state_dir().map(|dir| dir.join("last_version"))
+}
+
+/// Release notes to show if this is the first run after an upgrade.
+///
+/// Records the current version either way. A first run ever shows nothing,
+/// as there is nothing new to someone who just installed.
+pub fn after_upgrade() -> Option<String> {
+    let path = last_version_path()?;
+    let last = fs::read_to_string(&path).ok();
+    if last.as_deref().map(str::trim) != Some(VERSION) {
+        // Failing to record only means the notes show again next time
+        let _ = persist::write(&path, VERSION);
+    }
+    let notes = notes_since(CHANGELOG, last?.trim(), VERSION);
+    (!notes.is_empty()).then_some(notes)
+}
+
+/// All release notes up to the running version.
+pub fn all() -> String {
+    notes_since(CHANGELOG, "0", VERSION)
+}
+
+/// The sections of `changelog` for versions after `last`, up to `current`.
+fn notes_since(changelog: &str, last: &str, current: &str) -> String {
+    let (last, current) = (parse_version(last), parse_version(current));
+    changelog
+        .split("\n## ")
+        .skip(1)
+        .filter(|section| {
+            let version = parse_version(section.lines().next().unwrap_or_default());
+            last < version && version <= current
+        })
+        .map(|section| format!("## {}", section.trim_end()))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// A version as comparable numbers, e.g. `0.10.1` as `[0, 10, 1]`.
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTES: &str =
+        "# Changelog\n\n## 0.10.0\n\n- Ten\n\n## 0.9.0\n\n- Nine\n\n## 0.8.0\n\n- Eight\n";
+
+    #[test]
+    fn shows_versions_since_last_run() {
+        assert_eq!(
+            notes_since(NOTES, "0.8.0", "0.10.0"),
+            "## 0.10.0\n\n- Ten\n\n## 0.9.0\n\n- Nine"
+        );
+        assert_eq!(notes_since(NOTES, "0.10.0", "0.10.0"), "");
+    }
+
+    #[test]
+    fn embedded_changelog_covers_this_version() {
+        assert!(all().starts_with(&format!("## {}", VERSION)));
+    }
+}